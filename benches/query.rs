@@ -0,0 +1,138 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use rhai::{packages::Package, Engine, Scope};
+use rhai_url::UrlPackage;
+use std::collections::HashSet;
+use url::Url;
+
+/**
+ * The query functions as they were before editing the raw query,
+ * decoding and encoding again every pair.
+ */
+mod legacy {
+    use super::*;
+
+    fn read_pairs(url: &Url) -> Vec<(String, String)> {
+        url.query_pairs()
+            .map(|(name, value)| (name.into_owned(), value.into_owned()))
+            .collect()
+    }
+
+    fn write_pairs(url: &mut Url, pairs: &[(String, String)]) {
+        if pairs.is_empty() {
+            url.set_query(None);
+        } else {
+            url.query_pairs_mut().clear().extend_pairs(pairs);
+        }
+    }
+
+    pub fn query_delete(url: &mut Url, key: &str) {
+        let query: Vec<(String, String)> = url
+            .query_pairs()
+            .filter(|(name, _)| name != key)
+            .map(|(name, value)| (name.into_owned(), value.into_owned()))
+            .collect();
+
+        url.query_pairs_mut().clear().extend_pairs(&query);
+
+        if let Some(q) = url.query() {
+            if q.is_empty() {
+                url.set_query(None)
+            }
+        }
+    }
+
+    pub fn query_set(url: &mut Url, key: &str, value: &str) {
+        query_delete(url, key);
+        url.query_pairs_mut().append_pair(key, value);
+    }
+
+    pub fn query_rename(url: &mut Url, from: &str, to: &str) {
+        let pairs: Vec<(String, String)> = url
+            .query_pairs()
+            .map(|(name, value)| match name == from {
+                true => (to.to_string(), value.into_owned()),
+                false => (name.into_owned(), value.into_owned()),
+            })
+            .collect();
+
+        write_pairs(url, &pairs);
+    }
+
+    pub fn query_sort(url: &mut Url) {
+        let mut pairs = read_pairs(url);
+        pairs.sort_by(|(a, _), (b, _)| a.cmp(b));
+        write_pairs(url, &pairs);
+    }
+
+    pub fn query_dedupe(url: &mut Url) {
+        let mut pairs = read_pairs(url);
+        let mut seen = HashSet::new();
+        pairs.retain(|(name, _)| seen.insert(name.clone()));
+        write_pairs(url, &pairs);
+    }
+}
+
+/// Creates a Url with `size` pairs, encoded as in signed Urls, and a repeated key.
+fn input(size: usize) -> Url {
+    let query = (0..size)
+        .map(|index| format!("X-Key-{index}=value%20{index}%2Fwith~encoding"))
+        .collect::<Vec<_>>()
+        .join("&");
+
+    Url::parse(&format!("https://test.dev/path?{query}&X-Key-1=again")).unwrap()
+}
+
+/// Creates an engine with the package and the legacy functions, prefixed with `legacy_`.
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    UrlPackage::new().register_into_engine(&mut engine);
+
+    engine
+        .register_fn("legacy_query_delete", legacy::query_delete)
+        .register_fn("legacy_query_set", legacy::query_set)
+        .register_fn("legacy_query_rename", legacy::query_rename)
+        .register_fn("legacy_query_sort", legacy::query_sort)
+        .register_fn("legacy_query_dedupe", legacy::query_dedupe);
+    engine
+}
+
+fn bench_query(c: &mut Criterion) {
+    let engine = engine();
+
+    let cases = [
+        ("query_delete", r#"("X-Key-1")"#),
+        ("query_set", r#"("X-Key-1", "new value")"#),
+        ("query_rename", r#"("X-Key-1", "X-Renamed")"#),
+        ("query_sort", "()"),
+        ("query_dedupe", "()"),
+    ];
+
+    for (name, args) in cases {
+        let mut group = c.benchmark_group(name);
+
+        for (variant, function) in [
+            ("current", name.to_string()),
+            ("legacy", format!("legacy_{name}")),
+        ] {
+            let ast = engine
+                .compile(format!("fn run(url) {{ url.{function}{args}; url }}"))
+                .unwrap();
+
+            for size in [4, 64] {
+                let url = input(size);
+                group.bench_with_input(BenchmarkId::new(variant, size), &url, |b, url| {
+                    b.iter(|| {
+                        engine
+                            .call_fn::<Url>(&mut Scope::new(), &ast, "run", (url.clone(),))
+                            .unwrap()
+                    })
+                });
+            }
+        }
+
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_query);
+criterion_main!(benches);
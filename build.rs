@@ -2,18 +2,76 @@
 
 use std::fs::File;
 
+// Rhai modules in the `rhai-url` package, mirroring the crate layout.
+#[cfg(all(feature = "metadata", feature = "array"))]
+#[allow(dead_code)]
+#[path = "src/batch.rs"]
+mod batch;
+#[cfg(all(feature = "metadata", feature = "array"))]
+#[path = "src/connection.rs"]
+mod connection;
+#[cfg(feature = "metadata")]
+#[allow(dead_code)]
+#[path = "src/error.rs"]
+mod error;
+#[cfg(feature = "metadata")]
+#[path = "src/file.rs"]
+mod file;
+#[cfg(feature = "metadata")]
+#[path = "src/iter.rs"]
+mod iter;
+#[cfg(all(feature = "metadata", feature = "array"))]
+#[path = "src/nested.rs"]
+mod nested;
+#[cfg(feature = "metadata")]
+#[allow(dead_code)]
+#[path = "src/options.rs"]
+mod options;
+#[cfg(feature = "metadata")]
+#[path = "src/origin.rs"]
+mod origin;
+#[cfg(feature = "metadata")]
+#[path = "src/path.rs"]
+mod path;
+#[cfg(feature = "metadata")]
+#[path = "src/query.rs"]
+mod query;
+#[cfg(feature = "metadata")]
+#[allow(dead_code)]
+#[path = "src/redact.rs"]
+mod redact;
+#[cfg(feature = "metadata")]
+#[allow(dead_code)]
+#[path = "src/scheme.rs"]
+mod scheme;
+#[cfg(feature = "metadata")]
+#[allow(dead_code)]
+#[path = "src/url.rs"]
+mod url;
+#[cfg(feature = "metadata")]
+#[path = "src/web.rs"]
+mod web;
+
 #[allow(unused)]
 fn main() {
     // Update if needed
     println!("cargo:rerun-if-changed=src");
     println!("cargo:rerun-if-changed=build.rs");
 
-    // Make empty file for documentation
-    let doc_file_path = std::env::var("OUT_DIR").unwrap() + "/rhai-url-docs.md";
-    let mut doc_file = File::create(doc_file_path).expect("create doc file");
+    // Make empty files for documentation
+    let out_dir = std::env::var("OUT_DIR").unwrap();
+    let mut doc_file =
+        File::create(out_dir.clone() + "/rhai-url-docs.md").expect("create doc file");
+    let mut definitions_file =
+        File::create(out_dir.clone() + "/rhai-url.d.rhai").expect("create definitions file");
+    let mut index_file = File::create(out_dir + "/rhai-url-docs.json").expect("create index file");
 
     #[cfg(feature = "metadata")]
-    doc_gen::generate_doc(&mut doc_file);
+    {
+        doc_gen::generate_doc(&mut doc_file);
+        doc_gen::generate_definitions(&mut definitions_file);
+        doc_gen::generate_index(&mut index_file);
+    }
 }
 
 #[cfg(feature = "metadata")]
@@ -23,11 +81,6 @@ mod doc_gen {
     use std::collections::HashMap;
     use std::io::Write;
 
-    // Rhai modules in the `rhai-url` package.
-    mod pkg {
-        include!("src/url.rs");
-    }
-
     #[derive(Serialize, Deserialize, Debug, Clone)]
     struct Metadata {
         #[serde(default)]
@@ -60,12 +113,19 @@ mod doc_gen {
 
         pub fn fmt_doc_comments(&self) -> Option<String> {
             self.docComments.clone().map(|dc| {
+                // only strip the comment markers, `file:///` urls are kept
                 dc.join("\n")
-                    .replace("/// ", "")
-                    .replace("///", "")
-                    .replace("/**", "")
-                    .replace("**/", "")
-                    .replace("**/", "")
+                    .lines()
+                    .map(|line| {
+                        let line = line.trim_start();
+                        let line = line
+                            .strip_prefix("/// ")
+                            .or_else(|| line.strip_prefix("///"))
+                            .unwrap_or(line);
+                        line.replace("/**", "").replace("**/", "")
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
             })
         }
 
@@ -120,17 +180,208 @@ mod doc_gen {
         (prefix, name, signature)
     }
 
-    pub fn generate_doc(writer: &mut impl Write) {
-        let mut engine = Engine::new();
+    /// Creates a module with every exported function of the package.
+    fn package_module() -> Module {
         let mut fs_module = Module::new();
-        combine_with_exported_module!(&mut fs_module, "rhai_url", pkg::url_module);
-        engine.register_global_module(fs_module.into());
+        fs_module.set_custom_type::<::url::Url>("Url");
+        fs_module.set_custom_type::<::url::Origin>("Origin");
+        fs_module.set_custom_type::<crate::iter::QueryPair>("QueryPair");
+        fs_module.set_custom_type::<crate::iter::QueryPairs>("QueryPairs");
+        fs_module.set_custom_type::<crate::iter::QueryParams>("QueryParams");
+        fs_module.set_custom_type::<crate::iter::PathSegments>("PathSegments");
+        // before the Url module, which documents the default `hash`
+        combine_with_exported_module!(&mut fs_module, "rhai_url_web", crate::web::web_module);
+        combine_with_exported_module!(&mut fs_module, "rhai_url", crate::url::url_module);
+        crate::scheme::register_functions(
+            &mut fs_module,
+            &crate::scheme::SchemeRegistry::new(),
+            true,
+        );
+        combine_with_exported_module!(&mut fs_module, "rhai_url_file", crate::file::file_module);
+        combine_with_exported_module!(
+            &mut fs_module,
+            "rhai_url_origin",
+            crate::origin::origin_module
+        );
+        combine_with_exported_module!(&mut fs_module, "rhai_url_query", crate::query::query_module);
+        combine_with_exported_module!(&mut fs_module, "rhai_url_path", crate::path::path_module);
+        #[cfg(feature = "array")]
+        combine_with_exported_module!(
+            &mut fs_module,
+            "rhai_url_nested",
+            crate::nested::nested_module
+        );
+        #[cfg(feature = "array")]
+        combine_with_exported_module!(&mut fs_module, "rhai_url_batch", crate::batch::batch_module);
+        #[cfg(feature = "array")]
+        combine_with_exported_module!(
+            &mut fs_module,
+            "rhai_url_connection",
+            crate::connection::connection_module
+        );
+        fs_module
+    }
+
+    /// Extracts the metadata of the package functions, without the standard packages.
+    fn function_list() -> Vec<DocFunc> {
+        let mut engine = Engine::new();
+        engine.register_global_module(package_module().into());
 
-        // Extract metadata
         let json_fns = engine.gen_fn_metadata_to_json(false).unwrap();
         let v: Metadata = serde_json::from_str(&json_fns).unwrap();
-        let function_list = v.functions;
+        v.functions
+    }
 
+    /// Maps a Rust type of the function metadata to its script type, `?` being any value.
+    ///
+    /// References are dropped, `Result<T, _>` is `T`, and the custom types keep their name.
+    fn script_type(rust: &str) -> String {
+        let rust = rust.trim();
+        let rust = rust
+            .strip_prefix("&mut ")
+            .or_else(|| rust.strip_prefix('&'))
+            .unwrap_or(rust)
+            .trim();
+
+        if let Some((outer, arguments)) = rust.split_once('<') {
+            let arguments = arguments.strip_suffix('>').unwrap_or(arguments);
+            return match outer.rsplit("::").next() {
+                // the error type is never seen by scripts
+                Some("Result") => script_type(first_argument(arguments)),
+                _ => "?".to_string(),
+            };
+        }
+
+        let name = rust.rsplit("::").next().unwrap_or(rust);
+        match name {
+            "str" | "String" | "ImmutableString" => "string",
+            "INT" | "i8" | "i16" | "i32" | "i64" | "u8" | "u16" | "u32" | "u64" | "usize" => "int",
+            "FLOAT" | "f32" | "f64" => "float",
+            "Array" => "array",
+            "Map" => "map",
+            "FnPtr" => "Fn",
+            "Dynamic" => "?",
+            name => name,
+        }
+        .to_string()
+    }
+
+    /// Gets the first type argument of a generic, ignoring the commas of nested generics.
+    fn first_argument(arguments: &str) -> &str {
+        let mut depth = 0;
+        for (index, c) in arguments.char_indices() {
+            match c {
+                '<' => depth += 1,
+                '>' => depth -= 1,
+                ',' if depth == 0 => return &arguments[..index],
+                _ => {}
+            }
+        }
+        arguments
+    }
+
+    /// Writes the definition of a function, operators are written without parameter names.
+    fn fmt_definition(function: &DocFunc) -> String {
+        let (keyword, name) = match function.name.as_str() {
+            name if name.starts_with("get$") => ("fn get ", &name[4..]),
+            name if name.starts_with("set$") => ("fn set ", &name[4..]),
+            name if name.starts_with(|c: char| c.is_alphabetic() || c == '_') => ("fn ", name),
+            name => ("op ", name),
+        };
+
+        let params = function
+            .params
+            .iter()
+            .flatten()
+            .map(|param| {
+                let ty = script_type(param.get("type").map_or("?", String::as_str));
+                match (keyword, param.get("name")) {
+                    ("op ", _) | (_, None) => ty,
+                    (_, Some(name)) => format!("{name}: {ty}"),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        let return_type = script_type(function.returnType.as_deref().unwrap_or("()"));
+
+        format!("{keyword}{name}({params}) -> {return_type};")
+    }
+
+    /// Writes the `.d.rhai` definitions of the package functions, from their metadata.
+    pub fn generate_definitions(writer: &mut impl Write) {
+        let mut definitions = String::from("module static;\n");
+
+        for function in function_list() {
+            if function.name.starts_with("anon$") {
+                continue;
+            }
+
+            definitions.push('\n');
+            for comment in function.docComments.iter().flatten() {
+                for line in comment.lines() {
+                    definitions += line.trim();
+                    definitions.push('\n');
+                }
+            }
+            definitions += &fmt_definition(&function);
+            definitions.push('\n');
+        }
+
+        writer
+            .write_all(definitions.as_bytes())
+            .expect("Cannot write to definitions file");
+    }
+
+    /// Writes a JSON index of the package functions, with their signature, documentation and examples.
+    pub fn generate_index(writer: &mut impl Write) {
+        let index: Vec<_> = function_list()
+            .iter()
+            .filter(|function| !function.name.starts_with("anon$"))
+            .map(|function| {
+                let (prefix, name, signature) =
+                    fmt_fn_name(&function.name, function.fmt_signature());
+                let doc = function.fmt_doc_comments().unwrap_or_default();
+
+                serde_json::json!({
+                    "name": name,
+                    "kind": match prefix.trim() {
+                        "" => "function",
+                        "property get" => "getter",
+                        "property set" => "setter",
+                        _ => "indexer",
+                    },
+                    "signature": signature,
+                    "doc": doc,
+                    "examples": fmt_examples(&doc),
+                })
+            })
+            .collect();
+
+        serde_json::to_writer_pretty(writer, &index).expect("Cannot write to index file");
+    }
+
+    /// Extracts the ```js code blocks of the documentation.
+    fn fmt_examples(doc: &str) -> Vec<String> {
+        let mut examples = Vec::new();
+        let mut example: Option<Vec<&str>> = None;
+
+        for line in doc.lines() {
+            match (&mut example, line.trim()) {
+                (None, "```js") => example = Some(Vec::new()),
+                (Some(lines), "```") => {
+                    examples.push(lines.join("\n"));
+                    example = None;
+                }
+                (Some(lines), _) => lines.push(line),
+                (None, _) => {}
+            }
+        }
+
+        examples
+    }
+
+    pub fn generate_doc(writer: &mut impl Write) {
+        let function_list = function_list();
         // Write functions
         let mut indented = false;
         for (idx, function) in function_list.iter().enumerate() {
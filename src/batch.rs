@@ -0,0 +1,366 @@
+#[allow(unused_imports)]
+use rhai::plugin::*;
+
+use rhai::{Array, Map};
+use std::collections::{BTreeMap, HashSet};
+use url::Url;
+
+use crate::error::UrlErrorKind;
+use crate::options::UrlOptions;
+
+/// Options of `urls_normalize`, deciding which parts of the Urls are rewritten.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NormalizeOptions {
+    /// Removes the fragment, enabled by default.
+    pub remove_fragment: bool,
+    /// Sorts the query pairs by key, enabled by default.
+    pub sort_query: bool,
+    /// Removes an empty query string, a bare `?`, enabled by default.
+    pub remove_empty_query: bool,
+    /// Removes the trailing slash of the path, except for the root path, disabled by default.
+    pub remove_trailing_slash: bool,
+    /// Query keys removed from the Urls, such as `utm_source`.
+    pub remove_query_keys: Vec<String>,
+}
+
+impl Default for NormalizeOptions {
+    fn default() -> Self {
+        Self {
+            remove_fragment: true,
+            sort_query: true,
+            remove_empty_query: true,
+            remove_trailing_slash: false,
+            remove_query_keys: Vec::new(),
+        }
+    }
+}
+
+impl NormalizeOptions {
+    /// Reads the options from a Rhai map, missing keys keep their default.
+    pub fn from_map(map: &Map) -> Result<Self, Box<EvalAltResult>> {
+        let mut options = Self::default();
+
+        for (name, value) in map.iter() {
+            let flag = || {
+                value
+                    .as_bool()
+                    .map_err(|t| format!("option '{name}' must be a boolean, not {t}"))
+            };
+
+            match name.as_str() {
+                "remove_fragment" => options.remove_fragment = flag()?,
+                "sort_query" => options.sort_query = flag()?,
+                "remove_empty_query" => options.remove_empty_query = flag()?,
+                "remove_trailing_slash" => options.remove_trailing_slash = flag()?,
+                "remove_query_keys" => {
+                    options.remove_query_keys = value
+                        .clone()
+                        .into_typed_array::<ImmutableString>()
+                        .map_err(|_| format!("option '{name}' must be an array of strings"))?
+                        .into_iter()
+                        .map(Into::into)
+                        .collect()
+                }
+                _ => return Err(format!("unknown option '{name}'").into()),
+            }
+        }
+
+        Ok(options)
+    }
+
+    /// Returns a normalized copy of the Url.
+    pub fn normalize(&self, url: &Url) -> Url {
+        let mut url = url.clone();
+
+        if self.remove_fragment {
+            url.set_fragment(None);
+        }
+
+        if self.remove_trailing_slash && url.path().len() > 1 && url.path().ends_with('/') {
+            let path = url.path().trim_end_matches('/').to_string();
+            url.set_path(&path);
+        }
+
+        if url.query().is_some() && (self.sort_query || !self.remove_query_keys.is_empty()) {
+            // the kept pairs keep their encoding
+            crate::query::retain_pairs(
+                &mut url,
+                |segment| {
+                    let key = crate::query::raw_key(segment);
+                    !self.remove_query_keys.iter().any(|removed| *removed == key)
+                },
+                crate::query::NO_PAIRS,
+            );
+            if self.sort_query {
+                crate::query::sort_pairs(&mut url);
+            }
+        }
+
+        if self.remove_empty_query && url.query() == Some("") {
+            url.set_query(None);
+        }
+
+        url
+    }
+}
+
+/// Converts a Url or a string, `None` for invalid entries.
+fn convert(options: &UrlOptions, value: &Dynamic) -> Option<Url> {
+    options.to_url(value).ok()
+}
+
+/// Returns `true` if the text matches a pattern where `*` matches any characters.
+fn matches(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // no `*`, the whole text must match
+        return rest.is_empty();
+    };
+
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+/// Parses every entry, collecting the Urls and the errors of the invalid entries.
+pub fn parse_urls(options: &UrlOptions, inputs: Array) -> Map {
+    let mut urls = Array::new();
+    let mut errors = Array::new();
+
+    for (index, input) in inputs.into_iter().enumerate() {
+        match options.to_url(&input) {
+            Ok(url) => urls.push(Dynamic::from(url)),
+            Err(err) => {
+                let kind = UrlErrorKind::of(&err).unwrap_or(UrlErrorKind::InvalidUrl);
+                let message = match *err {
+                    EvalAltResult::ErrorRuntime(ref value, _) => value
+                        .read_lock::<Map>()
+                        .and_then(|map| map.get("message").map(ToString::to_string)),
+                    _ => None,
+                }
+                .unwrap_or_else(|| err.to_string());
+
+                let mut error = Map::new();
+                error.insert("index".into(), (index as rhai::INT).into());
+                error.insert("input".into(), input);
+                error.insert("kind".into(), kind.as_str().into());
+                error.insert("message".into(), message.into());
+                errors.push(error.into());
+            }
+        }
+    }
+
+    let mut result = Map::new();
+    result.insert("urls".into(), urls.into());
+    result.insert("errors".into(), errors.into());
+    result
+}
+
+/// Resolves every entry relative to a base, `()` for invalid entries.
+pub fn urls_join(options: &UrlOptions, base: &Url, inputs: Array) -> Array {
+    inputs
+        .iter()
+        .map(|input| {
+            let joined = match input.read_lock::<ImmutableString>() {
+                Some(input) => options.join(base, &input).ok(),
+                None => convert(options, input),
+            };
+            joined.map_or(Dynamic::UNIT, Dynamic::from)
+        })
+        .collect()
+}
+
+/// Normalizes every entry, `()` for invalid entries.
+pub fn urls_normalize(options: &UrlOptions, inputs: Array, normalize: &NormalizeOptions) -> Array {
+    inputs
+        .iter()
+        .map(|input| {
+            convert(options, input).map_or(Dynamic::UNIT, |url| {
+                Dynamic::from(normalize.normalize(&url))
+            })
+        })
+        .collect()
+}
+
+/// Removes the repeated Urls, keeping the first one, invalid entries are dropped.
+pub fn urls_dedupe(options: &UrlOptions, inputs: Array) -> Array {
+    let mut seen = HashSet::new();
+
+    inputs
+        .iter()
+        .filter_map(|input| convert(options, input))
+        .filter(|url| seen.insert(url.as_str().to_string()))
+        .map(Dynamic::from)
+        .collect()
+}
+
+/// Groups the Urls by host, invalid entries are dropped.
+pub fn urls_group_by_host(options: &UrlOptions, inputs: Array) -> Map {
+    let mut groups = BTreeMap::<String, Array>::new();
+
+    for url in inputs.iter().filter_map(|input| convert(options, input)) {
+        let host = url.host_str().unwrap_or_default().to_string();
+        groups.entry(host).or_default().push(Dynamic::from(url));
+    }
+
+    groups
+        .into_iter()
+        .map(|(host, urls)| (host.into(), urls.into()))
+        .collect()
+}
+
+/// Keeps the Urls matching a pattern, invalid entries are dropped.
+pub fn urls_filter_by_pattern(options: &UrlOptions, inputs: Array, pattern: &str) -> Array {
+    inputs
+        .iter()
+        .filter_map(|input| convert(options, input))
+        .filter(|url| matches(pattern, url.as_str()))
+        .map(Dynamic::from)
+        .collect()
+}
+
+#[export_module]
+pub mod batch_module {
+    use super::NormalizeOptions;
+    use crate::options::UrlOptions;
+    use rhai::{Array, Map};
+
+    /// Parses an array of strings, returning a map with the parsed `urls` and the `errors`.
+    ///
+    /// Each error is a map with the `index` and the `input` of the entry, and the `kind` and
+    /// the `message` of the error. Urls in the array are kept.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// let result = parse_urls(["http://test.dev/", "not a url"]);
+    ///
+    /// result.urls.len(); // 1
+    /// result.errors[0].index; // 1
+    /// result.errors[0].kind; // 'invalid_url'
+    /// ```
+    pub fn parse_urls(inputs: Array) -> Map {
+        super::parse_urls(&UrlOptions::default(), inputs)
+    }
+
+    /// Resolves an array of relative Urls against a base Url or string.
+    ///
+    /// Entries that cannot be resolved are `()`, so that the result has the same positions.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// let urls = urls_join("http://test.dev/a/", ["b", "/c", "https://example.com/"]);
+    ///
+    /// urls[0] == "http://test.dev/a/b"
+    /// urls[1] == "http://test.dev/c"
+    /// urls[2] == "https://example.com/"
+    /// ```
+    #[rhai_fn(return_raw)]
+    pub fn urls_join(base: Dynamic, inputs: Array) -> Result<Array, Box<EvalAltResult>> {
+        let options = UrlOptions::default();
+        let base = options.to_url(&base)?;
+        Ok(super::urls_join(&options, &base, inputs))
+    }
+
+    /// Normalizes an array of Urls or strings, removing the fragment, the empty query string
+    /// and sorting the query pairs.
+    ///
+    /// Entries that cannot be parsed are `()`, so that the result has the same positions.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// let urls = urls_normalize(["HTTP://Test.dev:80/?b=2&a=1#top"]);
+    ///
+    /// urls[0] == "http://test.dev/?a=1&b=2"
+    /// ```
+    pub fn urls_normalize(inputs: Array) -> Array {
+        super::urls_normalize(&UrlOptions::default(), inputs, &NormalizeOptions::default())
+    }
+
+    /// Normalizes an array of Urls or strings with options.
+    ///
+    /// * `remove_fragment`: removes the fragment, `true` by default
+    /// * `sort_query`: sorts the query pairs by key, `true` by default
+    /// * `remove_empty_query`: removes a bare `?`, `true` by default
+    /// * `remove_trailing_slash`: removes the trailing slash of the path, `false` by default
+    /// * `remove_query_keys`: query keys to remove, such as `utm_source`
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// let urls = urls_normalize(["http://test.dev/a/?utm_source=x&q=1"], #{ remove_trailing_slash: true, remove_query_keys: ["utm_source"] });
+    ///
+    /// urls[0] == "http://test.dev/a?q=1"
+    /// ```
+    #[rhai_fn(name = "urls_normalize", return_raw)]
+    pub fn urls_normalize_with(inputs: Array, options: Map) -> Result<Array, Box<EvalAltResult>> {
+        let normalize = NormalizeOptions::from_map(&options)?;
+        Ok(super::urls_normalize(
+            &UrlOptions::default(),
+            inputs,
+            &normalize,
+        ))
+    }
+
+    /// Removes the repeated Urls of an array of Urls or strings, keeping the first one.
+    ///
+    /// Urls are compared once parsed, so `HTTP://test.dev:80` and `http://test.dev/` are the
+    /// same Url. Entries that cannot be parsed are dropped.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// let urls = urls_dedupe(["http://test.dev/", "HTTP://test.dev:80", "http://test.dev/a"]);
+    ///
+    /// urls.len(); // 2
+    /// ```
+    pub fn urls_dedupe(inputs: Array) -> Array {
+        super::urls_dedupe(&UrlOptions::default(), inputs)
+    }
+
+    /// Groups an array of Urls or strings by host, in a map of arrays of Urls.
+    ///
+    /// Urls without a host are grouped under an empty string, entries that cannot be parsed
+    /// are dropped.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// let groups = urls_group_by_host(["http://a.dev/1", "http://b.dev/", "https://a.dev/2"]);
+    ///
+    /// groups["a.dev"].len(); // 2
+    /// groups["b.dev"].len(); // 1
+    /// ```
+    pub fn urls_group_by_host(inputs: Array) -> Map {
+        super::urls_group_by_host(&UrlOptions::default(), inputs)
+    }
+
+    /// Keeps the Urls of an array of Urls or strings matching a pattern.
+    ///
+    /// The pattern is matched against the whole Url, `*` matches any characters.
+    /// Entries that cannot be parsed are dropped.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// let urls = urls_filter_by_pattern(["https://a.test.dev/x", "https://test.dev/y"], "https://*.test.dev/*");
+    ///
+    /// urls.len(); // 1
+    /// ```
+    pub fn urls_filter_by_pattern(inputs: Array, pattern: &str) -> Array {
+        super::urls_filter_by_pattern(&UrlOptions::default(), inputs, pattern)
+    }
+}
@@ -0,0 +1,27 @@
+/// Rhai definitions of the package functions, for editor autocompletion and language servers.
+///
+/// Generated by `build.rs` with `Engine::definitions`, as a single `.d.rhai` file.
+///
+/// # Example
+///
+/// ```rust
+/// std::fs::write(
+///     std::env::temp_dir().join("rhai-url.d.rhai"),
+///     rhai_url::definitions(),
+/// )?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[must_use]
+pub fn definitions() -> &'static str {
+    include_str!(concat!(env!("OUT_DIR"), "/rhai-url.d.rhai"))
+}
+
+/// JSON index of the package functions, generated by `build.rs`.
+///
+/// The index is an array with an entry per function overload, holding its `name`, its `kind`
+/// (`function`, `getter`, `setter` or `indexer`), its `signature`, its `doc` in Markdown
+/// and the `examples` extracted from the ```` ```js ```` blocks of the doc.
+#[must_use]
+pub fn function_index() -> &'static str {
+    include_str!(concat!(env!("OUT_DIR"), "/rhai-url-docs.json"))
+}
@@ -0,0 +1,60 @@
+use rhai::{Dynamic, EvalAltResult, Map, Position};
+
+/// Kinds of errors raised by the package.
+///
+/// The error is raised as a runtime error holding a map with the `kind` and `message` keys,
+/// so that scripts can tell them apart in a `catch` block:
+///
+/// ```js
+/// try {
+///     let url = Url("javascript:alert(1)");
+/// } catch (err) {
+///     if err.kind == "scheme_not_allowed" {
+///         print(err.message);
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UrlErrorKind {
+    /// The value cannot be parsed as a Url.
+    InvalidUrl,
+    /// The scheme is denied or not in the allowed schemes.
+    SchemeNotAllowed,
+    /// A resource limit is exceeded.
+    LimitExceeded,
+}
+
+impl UrlErrorKind {
+    /// The name of the kind, as seen by scripts.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::InvalidUrl => "invalid_url",
+            Self::SchemeNotAllowed => "scheme_not_allowed",
+            Self::LimitExceeded => "limit_exceeded",
+        }
+    }
+
+    /// Creates an error of this kind.
+    pub fn error(self, message: impl Into<String>) -> Box<EvalAltResult> {
+        let mut map = Map::new();
+        map.insert("kind".into(), self.as_str().into());
+        map.insert("message".into(), message.into().into());
+
+        EvalAltResult::ErrorRuntime(Dynamic::from_map(map), Position::NONE).into()
+    }
+
+    /// Returns the kind of an error raised by the package, if any.
+    pub fn of(error: &EvalAltResult) -> Option<Self> {
+        let EvalAltResult::ErrorRuntime(value, _) = error else {
+            return None;
+        };
+        let map = value.read_lock::<Map>()?;
+
+        match map.get("kind")?.clone().into_string().ok()?.as_str() {
+            "invalid_url" => Some(Self::InvalidUrl),
+            "scheme_not_allowed" => Some(Self::SchemeNotAllowed),
+            "limit_exceeded" => Some(Self::LimitExceeded),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,59 @@
+#[allow(unused_imports)]
+use rhai::plugin::*;
+
+#[export_module]
+pub mod file_module {
+    use url::Url;
+
+    /// Creates a `file:` Url from an absolute file path.
+    ///
+    /// Only registered when file path conversion is enabled in the package builder.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// let url = url_from_file_path("/tmp/file name.txt");
+    ///
+    /// url == "file:///tmp/file%20name.txt"
+    /// ```
+    #[rhai_fn(return_raw)]
+    pub fn url_from_file_path(path: &str) -> Result<Url, Box<EvalAltResult>> {
+        Url::from_file_path(path)
+            .map_err(|_| format!("'{path}' is not an absolute file path").into())
+    }
+
+    /// Creates a `file:` Url from an absolute directory path, with a trailing slash.
+    ///
+    /// Only registered when file path conversion is enabled in the package builder.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// let url = url_from_directory_path("/tmp");
+    ///
+    /// url == "file:///tmp/"
+    /// ```
+    #[rhai_fn(return_raw)]
+    pub fn url_from_directory_path(path: &str) -> Result<Url, Box<EvalAltResult>> {
+        Url::from_directory_path(path)
+            .map_err(|_| format!("'{path}' is not an absolute directory path").into())
+    }
+
+    /// Converts a `file:` Url into a file path.
+    ///
+    /// Only registered when file path conversion is enabled in the package builder.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// let url = Url("file:///tmp/file%20name.txt");
+    ///
+    /// url.to_file_path(); // "/tmp/file name.txt"
+    /// ```
+    #[rhai_fn(global, pure, return_raw)]
+    pub fn to_file_path(url: &mut Url) -> Result<ImmutableString, Box<EvalAltResult>> {
+        url.to_file_path()
+            .map(|path| path.to_string_lossy().into_owned().into())
+            .map_err(|_| format!("'{url}' is not a file path").into())
+    }
+}
@@ -0,0 +1,159 @@
+use rhai::ImmutableString;
+use url::{form_urlencoded, Url};
+
+/// A single decoded key/value pair of a query string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryPair {
+    /// The decoded key.
+    pub key: ImmutableString,
+    /// The decoded value.
+    pub value: ImmutableString,
+}
+
+/// Iterable view over the query pairs of a Url.
+///
+/// The pairs are decoded lazily, one at a time, while iterating.
+#[derive(Debug, Clone)]
+pub struct QueryPairs {
+    /// The raw query string, without the leading '?'.
+    query: String,
+}
+
+impl QueryPairs {
+    /// Creates a view over the query pairs of the given Url.
+    pub fn new(url: &Url) -> Self {
+        Self {
+            query: url.query().unwrap_or("").to_string(),
+        }
+    }
+}
+
+impl IntoIterator for QueryPairs {
+    type Item = QueryPair;
+    type IntoIter = QueryPairsIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        QueryPairsIter {
+            query: self.query,
+            position: 0,
+        }
+    }
+}
+
+/// Iterator returned by [`QueryPairs`].
+#[derive(Debug, Clone)]
+pub struct QueryPairsIter {
+    /// The raw query string.
+    query: String,
+    /// Byte offset of the next pair to decode.
+    position: usize,
+}
+
+impl Iterator for QueryPairsIter {
+    type Item = QueryPair;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.position < self.query.len() {
+            let rest = &self.query[self.position..];
+            let end = rest.find('&').unwrap_or(rest.len());
+            self.position += end + 1;
+
+            // empty segments such as `a=1&&b=2` are skipped by the parser
+            if let Some((key, value)) = form_urlencoded::parse(&rest.as_bytes()[..end]).next() {
+                return Some(QueryPair {
+                    key: key.as_ref().into(),
+                    value: value.as_ref().into(),
+                });
+            }
+        }
+
+        None
+    }
+}
+
+/// View over the query parameters of a Url, read and written with the indexer.
+///
+/// Scripts get it from the `params` property, changes made with `url.params[key] = value`
+/// are written back to the Url by the `params` setter.
+#[derive(Debug, Clone)]
+pub struct QueryParams {
+    /// A copy of the Url holding the query.
+    pub(crate) url: Url,
+}
+
+impl QueryParams {
+    /// Creates a view over the query parameters of the given Url.
+    pub fn new(url: &Url) -> Self {
+        Self { url: url.clone() }
+    }
+}
+
+impl IntoIterator for QueryParams {
+    type Item = QueryPair;
+    type IntoIter = QueryPairsIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        QueryPairs::new(&self.url).into_iter()
+    }
+}
+
+/// Iterable view over the path segments of a Url.
+///
+/// Segments are returned percent-encoded, as they appear in the Url.
+#[derive(Debug, Clone)]
+pub struct PathSegments {
+    /// The raw path, `None` for cannot-be-a-base Urls.
+    path: Option<String>,
+}
+
+impl PathSegments {
+    /// Creates a view over the path segments of the given Url.
+    pub fn new(url: &Url) -> Self {
+        Self {
+            path: url
+                .path_segments()
+                .map(|_| url.path().trim_start_matches('/').to_string()),
+        }
+    }
+}
+
+impl IntoIterator for PathSegments {
+    type Item = ImmutableString;
+    type IntoIter = PathSegmentsIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        PathSegmentsIter {
+            position: if self.path.is_some() { 0 } else { usize::MAX },
+            path: self.path.unwrap_or_default(),
+        }
+    }
+}
+
+/// Iterator returned by [`PathSegments`].
+#[derive(Debug, Clone)]
+pub struct PathSegmentsIter {
+    /// The raw path, without the leading '/'.
+    path: String,
+    /// Byte offset of the next segment, `usize::MAX` once exhausted.
+    position: usize,
+}
+
+impl Iterator for PathSegmentsIter {
+    type Item = ImmutableString;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.position > self.path.len() {
+            return None;
+        }
+
+        let rest = &self.path[self.position..];
+        let end = rest.find('/').unwrap_or(rest.len());
+        let segment = rest[..end].into();
+        self.position = match rest.len() > end {
+            true => self.position + end + 1,
+            false => usize::MAX,
+        };
+
+        Some(segment)
+    }
+}
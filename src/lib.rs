@@ -10,14 +10,59 @@
 #![doc = include_str!(concat!(env!("OUT_DIR"), "/rhai-url-docs.md"))]
 #![doc = include_str!("../docs/highlight.html")]
 
-use rhai::def_package;
-use rhai::plugin::*;
-
+/// Functions over arrays of Urls.
+#[cfg(feature = "array")]
+pub(crate) mod batch;
+/// Database connection string helpers.
+#[cfg(feature = "array")]
+pub(crate) mod connection;
+/// Generated definitions and function index.
+#[cfg(feature = "metadata")]
+pub(crate) mod docs;
+/// Errors raised to scripts.
+pub(crate) mod error;
+/// File path conversion functions.
+#[cfg(any(
+    unix,
+    windows,
+    target_os = "redox",
+    target_os = "wasi",
+    target_os = "hermit"
+))]
+pub(crate) mod file;
+/// Views over query pairs, query parameters and path segments.
+pub(crate) mod iter;
+/// Nested query string codec, using the bracket syntax.
+#[cfg(feature = "array")]
+pub(crate) mod nested;
+/// Package options.
+pub(crate) mod options;
+/// Origin and same-site comparisons.
+pub(crate) mod origin;
+/// The package and its builder.
+pub(crate) mod package;
+/// Path functions.
+pub(crate) mod path;
+/// Query string functions.
+pub(crate) mod query;
+/// Url redaction for logging.
+pub(crate) mod redact;
+/// Custom schemes registry.
+pub(crate) mod scheme;
+/// Url constructor, getters, setters and transformations.
 pub(crate) mod url;
+/// Properties of the browser `URL` object.
+pub(crate) mod web;
 
-def_package! {
-    /// Package for url operations.
-    pub UrlPackage(lib) {
-       combine_with_exported_module!(lib, "rhai_url", url::url_module);
-    }
-}
+pub use self::url::to_url;
+#[cfg(feature = "metadata")]
+pub use docs::{definitions, function_index};
+pub use error::UrlErrorKind;
+pub use iter::{PathSegments, QueryPair, QueryPairs, QueryParams};
+#[cfg(feature = "array")]
+pub use nested::{NestedOptions, QueryEncoding};
+pub use options::{FunctionGroup, SetterMode, UrlOptions};
+pub use package::{UrlPackage, UrlPackageBuilder};
+pub use query::ArrayFormat;
+pub use redact::RedactOptions;
+pub use scheme::SchemeRegistry;
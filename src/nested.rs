@@ -0,0 +1,438 @@
+#[allow(unused_imports)]
+use rhai::plugin::*;
+
+use rhai::{Array, Map};
+use std::collections::BTreeMap;
+use url::form_urlencoded;
+
+use crate::query::{flatten_pairs, ArrayFormat};
+
+/// How keys and values are percent-encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum QueryEncoding {
+    /// `application/x-www-form-urlencoded`, spaces are written as `+`.
+    #[default]
+    Form,
+    /// RFC 3986 percent-encoding, spaces are written as `%20` and `+` is a literal plus.
+    Rfc3986,
+}
+
+/// Options of the nested query string codec, matching the defaults of `qs`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NestedOptions {
+    /// How arrays are written when stringifying.
+    pub array_format: ArrayFormat,
+    /// Maximum number of bracket segments parsed per key, the remainder is kept as a literal key.
+    pub depth: usize,
+    /// Maximum number of pairs parsed, extra pairs are ignored.
+    pub parameter_limit: usize,
+    /// Highest index parsed into an array, larger indices such as `a[100]` make a map.
+    pub array_limit: usize,
+    /// How keys and values are encoded and decoded.
+    pub encoding: QueryEncoding,
+}
+
+impl Default for NestedOptions {
+    fn default() -> Self {
+        Self {
+            array_format: ArrayFormat::Brackets,
+            depth: 5,
+            parameter_limit: 1000,
+            array_limit: 20,
+            encoding: QueryEncoding::Form,
+        }
+    }
+}
+
+impl NestedOptions {
+    /// Reads the options from a Rhai map, missing keys keep their default value.
+    ///
+    /// Recognized keys are `array_format`, `depth`, `parameter_limit`, `array_limit` and `encoding`.
+    pub fn from_map(map: &Map) -> Result<Self, Box<EvalAltResult>> {
+        let mut options = Self::default();
+
+        for (name, value) in map.iter() {
+            match name.as_str() {
+                "array_format" => {
+                    options.array_format = ArrayFormat::parse(&read_string(name, value)?)?
+                }
+                "depth" => options.depth = read_usize(name, value)?,
+                "parameter_limit" => options.parameter_limit = read_usize(name, value)?,
+                "array_limit" => options.array_limit = read_usize(name, value)?,
+                "encoding" => {
+                    options.encoding = match read_string(name, value)?.as_str() {
+                        "form" => QueryEncoding::Form,
+                        "rfc3986" => QueryEncoding::Rfc3986,
+                        encoding => {
+                            return Err(format!(
+                                "unknown encoding '{encoding}', expected 'form' or 'rfc3986'"
+                            )
+                            .into())
+                        }
+                    }
+                }
+                _ => return Err(format!("unknown option '{name}'").into()),
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+/// Reads a string option.
+fn read_string(name: &str, value: &Dynamic) -> Result<String, Box<EvalAltResult>> {
+    value
+        .clone()
+        .into_string()
+        .map_err(|t| format!("option '{name}' must be a string, not {t}").into())
+}
+
+/// Reads a non-negative integer option.
+fn read_usize(name: &str, value: &Dynamic) -> Result<usize, Box<EvalAltResult>> {
+    value
+        .as_int()
+        .ok()
+        .and_then(|value| usize::try_from(value).ok())
+        .ok_or_else(|| format!("option '{name}' must be a non-negative integer").into())
+}
+
+/// Intermediate tree built while parsing.
+enum Node {
+    /// A decoded value.
+    Leaf(String),
+    /// An array, from `key[]` or `key[0]`, by index and compacted into an array at the end.
+    List(BTreeMap<usize, Node>),
+    /// A map, from `key[name]`.
+    Object(BTreeMap<String, Node>),
+}
+
+impl Node {
+    /// Gets the array index of a key segment, `None` for names and indices above the limit.
+    fn index(segment: &str, array_limit: usize) -> Option<usize> {
+        segment
+            .parse::<usize>()
+            .ok()
+            .filter(|index| *index <= array_limit)
+    }
+
+    /// Creates the container that fits the next key segment.
+    fn container_for(segment: &str, array_limit: usize) -> Self {
+        match segment.is_empty() || Node::index(segment, array_limit).is_some() {
+            true => Node::List(BTreeMap::new()),
+            false => Node::Object(BTreeMap::new()),
+        }
+    }
+
+    /// Creates a node holding a value at the given key segments.
+    fn new_at(segments: &[String], value: String, array_limit: usize) -> Self {
+        match segments.first() {
+            Some(segment) => {
+                let mut node = Node::container_for(segment, array_limit);
+                node.insert(segments, value, array_limit);
+                node
+            }
+            None => Node::Leaf(value),
+        }
+    }
+
+    /// Gets the index following the last one of a list.
+    fn next_index(list: &BTreeMap<usize, Node>) -> usize {
+        list.last_key_value().map_or(0, |(index, _)| index + 1)
+    }
+
+    /// Gets the key following the largest numeric key of a map, so it never replaces one.
+    fn next_key(object: &BTreeMap<String, Node>) -> String {
+        let mut index = object
+            .keys()
+            .filter_map(|key| key.parse::<usize>().ok())
+            .max()
+            .map_or(Some(0), |index| index.checked_add(1))
+            .unwrap_or(object.len());
+        // only reached when the largest index overflows
+        while object.contains_key(&index.to_string()) {
+            index += 1;
+        }
+        index.to_string()
+    }
+
+    /// Inserts a value at the given key segments, below this node.
+    fn insert(&mut self, segments: &[String], value: String, array_limit: usize) {
+        let (segment, rest) = match segments.split_first() {
+            Some(split) => split,
+            None => return self.add_leaf(value),
+        };
+
+        // a scalar followed by nested keys, e.g. `a=1&a[]=2`, becomes a container
+        if let Node::Leaf(old) = self {
+            let old = Node::Leaf(std::mem::take(old));
+            *self = match Node::container_for(segment, array_limit) {
+                Node::List(_) => Node::List(BTreeMap::from([(0, old)])),
+                _ => Node::Object(BTreeMap::from([("0".to_string(), old)])),
+            };
+        }
+
+        // a named key on an array turns it into a map keyed by index, as `qs` does
+        if let Node::List(list) = self {
+            if !segment.is_empty() && Node::index(segment, array_limit).is_none() {
+                let object = std::mem::take(list)
+                    .into_iter()
+                    .map(|(index, node)| (index.to_string(), node))
+                    .collect();
+                *self = Node::Object(object);
+            }
+        }
+
+        match self {
+            Node::List(list) => {
+                let index = match segment.is_empty() {
+                    true => Node::next_index(list),
+                    false => Node::index(segment, array_limit).expect("array index"),
+                };
+                match list.get_mut(&index) {
+                    Some(node) => node.insert(rest, value, array_limit),
+                    None => {
+                        list.insert(index, Node::new_at(rest, value, array_limit));
+                    }
+                }
+            }
+            Node::Object(object) => {
+                let key = match segment.is_empty() {
+                    true => Node::next_key(object),
+                    false => segment.clone(),
+                };
+                match object.get_mut(&key) {
+                    Some(node) => node.insert(rest, value, array_limit),
+                    None => {
+                        object.insert(key, Node::new_at(rest, value, array_limit));
+                    }
+                }
+            }
+            Node::Leaf(_) => unreachable!("leaves are converted into containers above"),
+        }
+    }
+
+    /// Adds a value to this node, repeated keys are collected into an array.
+    fn add_leaf(&mut self, value: String) {
+        match self {
+            Node::Leaf(old) => {
+                let old = Node::Leaf(std::mem::take(old));
+                *self = Node::List(BTreeMap::from([(0, old), (1, Node::Leaf(value))]))
+            }
+            Node::List(list) => {
+                list.insert(Node::next_index(list), Node::Leaf(value));
+            }
+            // a map cannot hold an unnamed value, keep it under the next index
+            Node::Object(object) => {
+                object.insert(Node::next_key(object), Node::Leaf(value));
+            }
+        }
+    }
+
+    /// Converts the tree into Rhai values, arrays are compacted in index order.
+    fn into_dynamic(self) -> Dynamic {
+        match self {
+            Node::Leaf(value) => value.into(),
+            Node::List(list) => list
+                .into_values()
+                .map(Node::into_dynamic)
+                .collect::<Array>()
+                .into(),
+            Node::Object(object) => object
+                .into_iter()
+                .map(|(key, node)| (key.into(), node.into_dynamic()))
+                .collect::<Map>()
+                .into(),
+        }
+    }
+}
+
+/// Splits `a[b][c]` into `["a", "b", "c"]`, keeping at most `depth` bracket segments.
+///
+/// Segments beyond the depth are kept as a single literal segment, as `qs` does.
+fn split_key(key: &str, depth: usize) -> Vec<String> {
+    let open = match key.find('[') {
+        Some(open) if open > 0 => open,
+        _ => return vec![key.to_string()],
+    };
+
+    let mut segments = vec![key[..open].to_string()];
+    let mut rest = &key[open..];
+
+    while segments.len() <= depth && rest.starts_with('[') {
+        match rest.find(']') {
+            Some(close) => {
+                segments.push(rest[1..close].to_string());
+                rest = &rest[close + 1..];
+            }
+            None => break,
+        }
+    }
+
+    if !rest.is_empty() {
+        segments.push(rest.to_string());
+    }
+
+    segments
+}
+
+/// Parses a query string into nested maps and arrays.
+pub fn parse_nested(query: &str, options: &NestedOptions) -> Map {
+    let query = query.strip_prefix('?').unwrap_or(query);
+
+    // `+` is a literal plus under RFC 3986, protect it from the form decoder
+    let query = match options.encoding {
+        QueryEncoding::Form => query.to_string(),
+        QueryEncoding::Rfc3986 => query.replace('+', "%2B"),
+    };
+
+    let mut root = Node::Object(BTreeMap::new());
+
+    for (key, value) in form_urlencoded::parse(query.as_bytes()).take(options.parameter_limit) {
+        root.insert(
+            &split_key(&key, options.depth),
+            value.into_owned(),
+            options.array_limit,
+        );
+    }
+
+    root.into_dynamic().try_cast::<Map>().unwrap_or_default()
+}
+
+/// Serializes nested maps and arrays into a query string.
+pub fn stringify_nested(map: &Map, options: &NestedOptions) -> String {
+    let mut pairs = Vec::new();
+
+    for (key, value) in map.iter() {
+        flatten_pairs(key, value, options.array_format, &mut pairs);
+    }
+
+    let query = form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(&pairs)
+        .finish();
+
+    // the form serializer writes spaces as `+` and encodes a literal plus as `%2B`
+    match options.encoding {
+        QueryEncoding::Form => query,
+        QueryEncoding::Rfc3986 => query.replace('+', "%20"),
+    }
+}
+
+#[export_module]
+pub mod nested_module {
+    use super::{parse_nested, stringify_nested, NestedOptions};
+    use rhai::Map;
+    use url::Url;
+
+    /// Parses a query string using the bracket syntax of `qs`, Rails and PHP.
+    ///
+    /// `a[b]=1` becomes a map, `list[]=x` and repeated keys become arrays.
+    /// At most 5 levels and 1000 pairs are parsed.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// let query = query_parse_nested("a[b][c]=1&list[]=x&list[]=y");
+    ///
+    /// query.a.b.c; // "1"
+    /// query.list; // ["x", "y"]
+    /// ```
+    pub fn query_parse_nested(query: &str) -> Map {
+        parse_nested(query, &NestedOptions::default())
+    }
+
+    /// Parses a query string using the bracket syntax, with options.
+    ///
+    /// * `depth`: maximum number of nested levels per key, default 5
+    /// * `parameter_limit`: maximum number of pairs parsed, default 1000
+    /// * `array_limit`: highest index parsed into an array, larger ones make a map, default 20
+    /// * `encoding`: `form` (default) decodes `+` as a space, `rfc3986` keeps it as a plus
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// let query = query_parse_nested("a[b][c]=1", #{ depth: 1 });
+    ///
+    /// query.a.b["[c]"]; // "1"
+    /// ```
+    #[rhai_fn(name = "query_parse_nested", return_raw)]
+    pub fn query_parse_nested_with_options(
+        query: &str,
+        options: Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        Ok(parse_nested(query, &NestedOptions::from_map(&options)?))
+    }
+
+    /// Serializes a map into a query string using the bracket syntax.
+    ///
+    /// Arrays are written with empty brackets, `list[]=x&list[]=y`.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// let query = query_stringify_nested(#{ a: #{ b: 1 }, list: ["x", "y"] });
+    ///
+    /// query == "a%5Bb%5D=1&list%5B%5D=x&list%5B%5D=y"
+    /// ```
+    pub fn query_stringify_nested(map: Map) -> ImmutableString {
+        stringify_nested(&map, &NestedOptions::default()).into()
+    }
+
+    /// Serializes a map into a query string using the bracket syntax, with options.
+    ///
+    /// * `array_format`: `brackets` (default), `indices`, `repeat` or `comma`
+    /// * `encoding`: `form` (default) writes spaces as `+`, `rfc3986` writes them as `%20`
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// let options = #{ array_format: "indices", encoding: "rfc3986" };
+    /// let query = query_stringify_nested(#{ list: ["x y", "z"] }, options);
+    ///
+    /// query == "list%5B0%5D=x%20y&list%5B1%5D=z"
+    /// ```
+    #[rhai_fn(name = "query_stringify_nested", return_raw)]
+    pub fn query_stringify_nested_with_options(
+        map: Map,
+        options: Map,
+    ) -> Result<ImmutableString, Box<EvalAltResult>> {
+        Ok(stringify_nested(&map, &NestedOptions::from_map(&options)?).into())
+    }
+
+    /// Gets the query string parsed with the bracket syntax.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// let url = Url("http://test.dev/?filter[status]=open");
+    ///
+    /// url.query_nested.filter.status; // "open"
+    /// ```
+    #[rhai_fn(global, get = "query_nested", pure)]
+    pub fn query_nested(url: &mut Url) -> Map {
+        parse_nested(url.query().unwrap_or(""), &NestedOptions::default())
+    }
+
+    /// Sets the query string from a map, using the bracket syntax.
+    ///
+    /// An empty map removes the query string.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// let url = Url("http://test.dev/");
+    ///
+    /// url.query_nested = #{ filter: #{ status: "open" } };
+    ///
+    /// url == "http://test.dev/?filter%5Bstatus%5D=open"
+    /// ```
+    #[rhai_fn(global, set = "query_nested", pure)]
+    pub fn set_query_nested(url: &mut Url, value: Map) {
+        let query = stringify_nested(&value, &NestedOptions::default());
+
+        if query.is_empty() {
+            url.set_query(None)
+        } else {
+            url.set_query(Some(&query))
+        }
+    }
+}
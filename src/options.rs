@@ -0,0 +1,201 @@
+use rhai::{Dynamic, EvalAltResult, Position};
+use url::Url;
+
+use crate::error::UrlErrorKind::{InvalidUrl, LimitExceeded, SchemeNotAllowed};
+use crate::redact::RedactOptions;
+use crate::scheme::SchemeRegistry;
+
+/// How setters behave when `url::Url` refuses a new value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SetterMode {
+    /// The value is ignored and the Url is left unchanged.
+    #[default]
+    Lenient,
+    /// The setter raises an error.
+    Strict,
+}
+
+/// Groups of functions that can be registered by the package.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum FunctionGroup {
+    /// The `Url` constructor, getters, setters and `with_*` transformations.
+    Core,
+    /// Query string functions, such as `query_get` and `query_pairs`.
+    Query,
+    /// Path functions, such as `path_segments`.
+    Path,
+    /// Query string codecs, such as `query_parse_nested`.
+    Encoding,
+    /// Database connection string helpers, such as `connection_info`.
+    Connection,
+    /// Functions over arrays of Urls, such as `parse_urls` and `urls_dedupe`.
+    Batch,
+}
+
+impl FunctionGroup {
+    /// Every function group.
+    pub const ALL: [FunctionGroup; 6] = [
+        FunctionGroup::Core,
+        FunctionGroup::Query,
+        FunctionGroup::Path,
+        FunctionGroup::Encoding,
+        FunctionGroup::Connection,
+        FunctionGroup::Batch,
+    ];
+}
+
+/// Runtime options of the package, see [`UrlPackage::builder`][crate::UrlPackage::builder].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct UrlOptions {
+    /// How setters behave when a value is refused.
+    pub setter_mode: SetterMode,
+    /// Schemes accepted by the constructor and the `scheme` setter, `None` accepts any scheme.
+    pub allowed_schemes: Option<Vec<String>>,
+    /// Schemes refused by the constructor and the `scheme` setter, `None` refuses no scheme.
+    pub denied_schemes: Option<Vec<String>>,
+    /// Maximum length in bytes of a Url, `None` for no limit.
+    pub max_url_length: Option<usize>,
+    /// Maximum number of query pairs of a Url, `None` for no limit.
+    pub max_query_pairs: Option<usize>,
+    /// Maximum number of path segments of a Url, `None` for no limit.
+    pub max_path_segments: Option<usize>,
+    /// Custom schemes and their default ports.
+    pub schemes: SchemeRegistry,
+    /// Lets scripts register custom schemes with `register_scheme`, in a registry local to the package.
+    pub script_schemes: bool,
+    /// Redaction applied by `to_debug`, `None` prints the Url verbatim.
+    pub redact_debug: Option<RedactOptions>,
+    /// Makes the `domain`, `query`, `fragment` and `hash` getters return `()` for absent components.
+    pub absent_as_unit: bool,
+}
+
+impl UrlOptions {
+    /// Returns `true` if any resource limit is set.
+    pub fn has_limits(&self) -> bool {
+        self.max_url_length.is_some()
+            || self.max_query_pairs.is_some()
+            || self.max_path_segments.is_some()
+    }
+
+    /// Converts a Url-like value, either a Url or a string parsed with [`UrlOptions::parse`].
+    ///
+    /// Other values raise a data type mismatch error.
+    pub fn to_url(&self, value: &Dynamic) -> Result<Url, Box<EvalAltResult>> {
+        if let Some(url) = value.read_lock::<Url>() {
+            return Ok(url.clone());
+        }
+
+        match value.read_lock::<rhai::ImmutableString>() {
+            Some(input) => self.parse(&input),
+            None => Err(EvalAltResult::ErrorMismatchDataType(
+                "Url".into(),
+                value.type_name().into(),
+                Position::NONE,
+            )
+            .into()),
+        }
+    }
+
+    /// Parses a Url, enforcing the resource limits and the allowed schemes,
+    /// and dropping the registered default port of its scheme.
+    pub fn parse(&self, input: &str) -> Result<Url, Box<EvalAltResult>> {
+        // refuse oversized input before parsing it
+        self.check_length(input.len())?;
+
+        let url = Url::parse(input)
+            .map_err(|e| InvalidUrl.error(format!("invalid url '{input}': {e}")))?;
+        self.accept(url)
+    }
+
+    /// Resolves a Url relative to a base, with the same checks as [`UrlOptions::parse`].
+    pub fn join(&self, base: &Url, input: &str) -> Result<Url, Box<EvalAltResult>> {
+        self.check_length(input.len())?;
+
+        let url = base
+            .join(input)
+            .map_err(|e| InvalidUrl.error(format!("invalid url '{input}': {e}")))?;
+        self.accept(url)
+    }
+
+    /// Checks the scheme and the limits of a parsed Url, and normalizes its port.
+    fn accept(&self, mut url: Url) -> Result<Url, Box<EvalAltResult>> {
+        self.check_scheme(url.scheme())?;
+        self.schemes.normalize(&mut url);
+        self.check_limits(&url)?;
+
+        Ok(url)
+    }
+
+    /// Checks a length against the maximum Url length.
+    fn check_length(&self, length: usize) -> Result<(), Box<EvalAltResult>> {
+        match self.max_url_length {
+            Some(max) if length > max => {
+                Err(LimitExceeded.error(format!("url is longer than the limit of {max} bytes")))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks a Url against the resource limits.
+    pub fn check_limits(&self, url: &Url) -> Result<(), Box<EvalAltResult>> {
+        self.check_length(url.as_str().len())?;
+
+        if let Some(max) = self.max_query_pairs {
+            let pairs = url.query().map_or(0, |query| {
+                query.split('&').filter(|s| !s.is_empty()).count()
+            });
+            if pairs > max {
+                return Err(
+                    LimitExceeded.error(format!("query has more than the limit of {max} pairs"))
+                );
+            }
+        }
+
+        if let Some(max) = self.max_path_segments {
+            let segments = url.path_segments().map_or(0, |segments| segments.count());
+            if segments > max {
+                return Err(
+                    LimitExceeded.error(format!("path has more than the limit of {max} segments"))
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks that a scheme is not denied and, if restricted, allowed.
+    pub fn check_scheme(&self, scheme: &str) -> Result<(), Box<EvalAltResult>> {
+        let matches = |schemes: &[String]| schemes.iter().any(|s| s.eq_ignore_ascii_case(scheme));
+
+        if self.denied_schemes.as_deref().is_some_and(matches) {
+            return Err(SchemeNotAllowed.error(format!("scheme '{scheme}' is denied")));
+        }
+        if self
+            .allowed_schemes
+            .as_deref()
+            .is_some_and(|allowed| !matches(allowed))
+        {
+            return Err(SchemeNotAllowed.error(format!("scheme '{scheme}' is not allowed")));
+        }
+
+        Ok(())
+    }
+
+    /// Sets the scheme of a Url, honoring the allowed schemes and the setter mode.
+    ///
+    /// A scheme refused by the allowed or denied schemes raises an error in both modes.
+    pub fn set_scheme(&self, url: &mut Url, scheme: &str) -> Result<(), Box<EvalAltResult>> {
+        self.check_scheme(scheme)?;
+
+        match url.set_scheme(scheme) {
+            Ok(()) => {
+                self.schemes.normalize(url);
+                Ok(())
+            }
+            Err(_) if self.setter_mode == SetterMode::Strict => {
+                Err(format!("cannot set scheme '{scheme}'").into())
+            }
+            Err(_) => Ok(()),
+        }
+    }
+}
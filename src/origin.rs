@@ -0,0 +1,171 @@
+#[allow(unused_imports)]
+use rhai::plugin::*;
+
+use url::{Host, Url};
+
+/// Returns the site of a Url, its scheme and registrable domain.
+///
+/// The registrable domain is found with the Public Suffix List, a domain without one,
+/// such as `localhost` or a public suffix like `github.io`, is its own site.
+/// IP addresses are their own site.
+pub fn site(url: &Url) -> Option<(&str, String)> {
+    let host = match url.host()? {
+        Host::Domain(domain) => {
+            let domain = domain.trim_end_matches('.').to_ascii_lowercase();
+            match psl::domain_str(&domain) {
+                Some(registrable) => registrable.to_string(),
+                None => domain,
+            }
+        }
+        host => host.to_string(),
+    };
+
+    Some((url.scheme(), host))
+}
+
+#[export_module]
+pub mod origin_module {
+    use super::site;
+    use crate::options::UrlOptions;
+    use url::{Origin, Url};
+
+    /// Creates the origin of a Url string, such as `https://test.dev`.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// let allowed = [Origin("https://test.dev"), Origin("https://app.test.dev")];
+    ///
+    /// Url("https://test.dev/path").origin in allowed == true
+    /// ```
+    #[rhai_fn(name = "Origin", return_raw)]
+    pub fn new(url: &str) -> Result<Origin, Box<EvalAltResult>> {
+        Ok(UrlOptions::default().parse(url)?.origin())
+    }
+
+    /// Gets the Url origin, serialized as `scheme://host[:port]`, or `null` for opaque origins
+    /// such as `data:` or `file:` Urls.
+    ///
+    /// Origins are compared with `==` to other origins or strings, opaque origins are only
+    /// equal to themselves.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// let url = Url("https://test.dev:8443/path");
+    ///
+    /// url.origin == "https://test.dev:8443"
+    /// Url("data:text/plain,hello").origin.to_string() == "null"
+    /// ```
+    #[rhai_fn(global, get = "origin", pure)]
+    pub fn origin(url: &mut Url) -> Origin {
+        url.origin()
+    }
+
+    /// Returns `true` if the Url has a tuple origin, made of a scheme, a host and a port,
+    /// `false` for opaque origins.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// Url("https://test.dev/").is_tuple_origin == true
+    /// Url("mailto:dev@test.dev").is_tuple_origin == false
+    /// ```
+    #[rhai_fn(global, get = "is_tuple_origin", pure)]
+    pub fn is_tuple_origin(url: &mut Url) -> bool {
+        url.origin().is_tuple()
+    }
+
+    /// Returns `true` if the origin is a tuple origin, `false` for opaque origins.
+    #[rhai_fn(global, get = "is_tuple_origin", pure)]
+    pub fn origin_is_tuple(origin: &mut Origin) -> bool {
+        origin.is_tuple()
+    }
+
+    /// Returns `true` if both Urls have the same tuple origin: scheme, host and port.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// let url = Url("https://test.dev/a");
+    ///
+    /// url.is_same_origin(Url("https://test.dev:443/b")) == true
+    /// url.is_same_origin(Url("http://test.dev/a")) == false
+    /// ```
+    #[rhai_fn(global, pure)]
+    pub fn is_same_origin(url: &mut Url, other: Url) -> bool {
+        url.origin().is_tuple() && url.origin() == other.origin()
+    }
+
+    /// Returns `true` if the Url has the same tuple origin as a Url string.
+    #[rhai_fn(global, name = "is_same_origin", pure, return_raw)]
+    pub fn is_same_origin_str(url: &mut Url, other: &str) -> Result<bool, Box<EvalAltResult>> {
+        Ok(is_same_origin(url, UrlOptions::default().parse(other)?))
+    }
+
+    /// Returns `true` if both Urls are on the same site: the same scheme and registrable domain.
+    ///
+    /// The registrable domain is found with the Public Suffix List, so `a.github.io` and
+    /// `b.github.io` are different sites. IP addresses only match themselves.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// let url = Url("https://app.test.dev/");
+    ///
+    /// url.is_same_site(Url("https://api.test.dev/")) == true
+    /// url.is_same_site(Url("http://app.test.dev/")) == false
+    /// ```
+    #[rhai_fn(global, pure)]
+    pub fn is_same_site(url: &mut Url, other: Url) -> bool {
+        site(url).is_some_and(|site| Some(site) == super::site(&other))
+    }
+
+    /// Returns `true` if the Url is on the same site as a Url string.
+    #[rhai_fn(global, name = "is_same_site", pure, return_raw)]
+    pub fn is_same_site_str(url: &mut Url, other: &str) -> Result<bool, Box<EvalAltResult>> {
+        Ok(is_same_site(url, UrlOptions::default().parse(other)?))
+    }
+
+    /// Get the origin serialization, `null` for opaque origins.
+    #[rhai_fn(global, name = "to_string", name = "to_debug", pure)]
+    pub fn to_string(origin: &mut Origin) -> ImmutableString {
+        origin.ascii_serialization().into()
+    }
+
+    /// Returns `true` if both origins are equal, opaque origins are only equal to themselves.
+    #[rhai_fn(global, name = "==", pure)]
+    pub fn eq(origin: &mut Origin, other: Origin) -> bool {
+        *origin == other
+    }
+
+    /// Returns `true` if the origins are different.
+    #[rhai_fn(global, name = "!=", pure)]
+    pub fn ne(origin: &mut Origin, other: Origin) -> bool {
+        *origin != other
+    }
+
+    /// Returns `true` if the origin is a tuple origin with the given serialization.
+    #[rhai_fn(global, name = "==", pure)]
+    pub fn eq_str(origin: &mut Origin, other: &str) -> bool {
+        origin.is_tuple() && origin.ascii_serialization() == other
+    }
+
+    /// Returns `true` if the origin is not a tuple origin with the given serialization.
+    #[rhai_fn(global, name = "!=", pure)]
+    pub fn ne_str(origin: &mut Origin, other: &str) -> bool {
+        !eq_str(origin, other)
+    }
+
+    /// Returns `true` if the origin is a tuple origin with the given serialization.
+    #[rhai_fn(global, name = "==")]
+    pub fn str_eq(value: &str, origin: Origin) -> bool {
+        origin.is_tuple() && origin.ascii_serialization() == value
+    }
+
+    /// Returns `true` if the origin is not a tuple origin with the given serialization.
+    #[rhai_fn(global, name = "!=")]
+    pub fn str_ne(value: &str, origin: Origin) -> bool {
+        !str_eq(value, origin)
+    }
+}
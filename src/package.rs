@@ -0,0 +1,783 @@
+use rhai::packages::Package;
+use rhai::plugin::*;
+use rhai::{FuncRegistration, Shared};
+use std::collections::BTreeSet;
+use url::Url;
+
+use crate::options::{FunctionGroup, SetterMode, UrlOptions};
+use crate::redact::RedactOptions;
+use crate::scheme::SchemeRegistry;
+
+/// Package for url operations.
+///
+/// Use [`UrlPackage::new`] for the full set of functions with the default options,
+/// or [`UrlPackage::builder`] to configure it.
+#[derive(Debug, Clone)]
+pub struct UrlPackage(Shared<Module>);
+
+impl Package for UrlPackage {
+    fn init(module: &mut Module) {
+        register_groups(module, &FunctionGroup::ALL.into_iter().collect(), false);
+    }
+
+    fn init_engine(engine: &mut Engine) {
+        engine
+            .register_type_with_name::<Url>("Url")
+            .register_type_with_name::<url::Origin>("Origin")
+            .register_type_with_name::<crate::iter::QueryPair>("QueryPair")
+            .register_type_with_name::<crate::iter::QueryPairs>("QueryPairs")
+            .register_type_with_name::<crate::iter::QueryParams>("QueryParams")
+            .register_type_with_name::<crate::iter::PathSegments>("PathSegments");
+    }
+
+    fn as_shared_module(&self) -> Shared<Module> {
+        self.0.clone()
+    }
+}
+
+impl Default for UrlPackage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UrlPackage {
+    /// Create a new `UrlPackage` with every function group and the default options.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::builder().build()
+    }
+
+    /// Create a builder to configure the options and the registered functions.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rhai::Engine;
+    /// use rhai::packages::Package;
+    /// use rhai_url::{FunctionGroup, SetterMode, UrlPackage};
+    ///
+    /// let package = UrlPackage::builder()
+    ///     .setter_mode(SetterMode::Strict)
+    ///     .allowed_schemes(["https", "mailto"])
+    ///     .max_url_length(2048)
+    ///     .groups([FunctionGroup::Core, FunctionGroup::Query])
+    ///     .build();
+    ///
+    /// let mut engine = Engine::new();
+    /// package.register_into_engine(&mut engine);
+    ///
+    /// assert!(engine.eval::<url::Url>(r#"Url("javascript:alert(1)")"#).is_err());
+    /// ```
+    #[must_use]
+    pub fn builder() -> UrlPackageBuilder {
+        UrlPackageBuilder::default()
+    }
+}
+
+/// Builder for a configured [`UrlPackage`], see [`UrlPackage::builder`].
+#[derive(Debug, Clone)]
+pub struct UrlPackageBuilder {
+    /// Runtime options.
+    options: UrlOptions,
+    /// Function groups to register.
+    groups: BTreeSet<FunctionGroup>,
+    /// Whether file path conversion functions are registered.
+    file_paths: bool,
+    /// Whether the browser `URL` properties are registered.
+    web_compat: bool,
+}
+
+impl Default for UrlPackageBuilder {
+    fn default() -> Self {
+        Self {
+            options: UrlOptions::default(),
+            groups: FunctionGroup::ALL.into_iter().collect(),
+            file_paths: false,
+            web_compat: false,
+        }
+    }
+}
+
+impl UrlPackageBuilder {
+    /// Sets how setters behave when a value is refused, lenient by default.
+    #[must_use]
+    pub fn setter_mode(mut self, mode: SetterMode) -> Self {
+        self.options.setter_mode = mode;
+        self
+    }
+
+    /// Restricts the schemes accepted by the constructor, the `scheme` setter and `with_scheme`.
+    ///
+    /// Schemes are compared ignoring case, any scheme is accepted by default.
+    /// Refused schemes raise a `scheme_not_allowed` error, see [`UrlErrorKind`][crate::UrlErrorKind].
+    #[must_use]
+    pub fn allowed_schemes<S: Into<String>>(
+        mut self,
+        schemes: impl IntoIterator<Item = S>,
+    ) -> Self {
+        self.options.allowed_schemes = Some(schemes.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Refuses schemes in the constructor, the `scheme` setter and `with_scheme`.
+    ///
+    /// Schemes are compared ignoring case and the denied schemes take precedence
+    /// over the allowed ones. Refused schemes raise a `scheme_not_allowed` error.
+    #[must_use]
+    pub fn denied_schemes<S: Into<String>>(mut self, schemes: impl IntoIterator<Item = S>) -> Self {
+        self.options.denied_schemes = Some(schemes.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Sets the maximum length in bytes of a Url, unlimited by default.
+    ///
+    /// Enforced by the constructor, the setters, the query functions and the `with_*` functions.
+    /// Exceeded limits raise a `limit_exceeded` error.
+    #[must_use]
+    pub fn max_url_length(mut self, max: usize) -> Self {
+        self.options.max_url_length = Some(max);
+        self
+    }
+
+    /// Sets the maximum number of query pairs of a Url, unlimited by default.
+    ///
+    /// Enforced by the constructor, the setters, the query functions and the `with_*` functions.
+    #[must_use]
+    pub fn max_query_pairs(mut self, max: usize) -> Self {
+        self.options.max_query_pairs = Some(max);
+        self
+    }
+
+    /// Sets the maximum number of path segments of a Url, unlimited by default.
+    ///
+    /// Enforced by the constructor, the setters, the query functions and the `with_*` functions.
+    #[must_use]
+    pub fn max_path_segments(mut self, max: usize) -> Self {
+        self.options.max_path_segments = Some(max);
+        self
+    }
+
+    /// Registers the default port of a custom scheme, see [`SchemeRegistry::register`].
+    ///
+    /// Urls with a registered scheme omit the default port and report it with `effective_port`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the scheme is invalid or is a special scheme such as `http`.
+    #[must_use]
+    pub fn register_scheme(self, scheme: &str, port: u16) -> Self {
+        if let Err(err) = self.options.schemes.register(scheme, port) {
+            panic!("{err}");
+        }
+        self
+    }
+
+    /// Uses a shared registry of custom schemes, empty by default.
+    ///
+    /// The host can keep a clone of the registry to register schemes after the package is built.
+    #[must_use]
+    pub fn scheme_registry(mut self, schemes: SchemeRegistry) -> Self {
+        self.options.schemes = schemes;
+        self
+    }
+
+    /// Registers `register_scheme`, letting scripts register custom schemes, disabled by default.
+    ///
+    /// Schemes registered by scripts are local to the package: they are honored by every engine
+    /// using it, but never reach the registry of the host and cannot replace its schemes.
+    /// Build a package per tenant when scripts of different tenants must not see each other's schemes.
+    #[must_use]
+    pub fn script_schemes(mut self, enabled: bool) -> Self {
+        self.options.script_schemes = enabled;
+        self
+    }
+
+    /// Makes `to_debug`, and so `debug()`, mask the sensitive parts of Urls, disabled by default.
+    ///
+    /// `to_string` and `print()` are left verbatim, scripts can call `redacted` or `to_safe_string`.
+    #[must_use]
+    pub fn redact_debug(mut self, options: RedactOptions) -> Self {
+        self.options.redact_debug = Some(options);
+        self
+    }
+
+    /// Makes the `domain`, `query`, `fragment` and `hash` getters return `()` for absent components,
+    /// disabled by default.
+    ///
+    /// `http://test.dev/?` then has a `""` query while `http://test.dev/` has a `()` one.
+    #[must_use]
+    pub fn absent_as_unit(mut self, enabled: bool) -> Self {
+        self.options.absent_as_unit = enabled;
+        self
+    }
+
+    /// Sets the function groups to register, every group is registered by default.
+    #[must_use]
+    pub fn groups(mut self, groups: impl IntoIterator<Item = FunctionGroup>) -> Self {
+        self.groups = groups.into_iter().collect();
+        self
+    }
+
+    /// Registers `url_from_file_path`, `url_from_directory_path` and `to_file_path`, disabled by default.
+    ///
+    /// Requires the [`FunctionGroup::Core`] group.
+    #[must_use]
+    pub fn file_paths(mut self, enabled: bool) -> Self {
+        self.file_paths = enabled;
+        self
+    }
+
+    /// Registers the properties of the browser `URL` object, disabled by default.
+    ///
+    /// Adds `protocol`, `username`, `password`, `host`, `hostname`, `port`, `pathname`,
+    /// `search` and a `href` setter, built on `url::quirks`. `hash` is replaced by its
+    /// browser version, with the `#` delimiter. Setters accept values with or without
+    /// their delimiter and, as browsers do, ignore refused values unless the setter mode
+    /// is strict.
+    ///
+    /// Requires the [`FunctionGroup::Core`] group.
+    #[must_use]
+    pub fn web_compat(mut self, enabled: bool) -> Self {
+        self.web_compat = enabled;
+        self
+    }
+
+    /// Builds the package.
+    #[must_use]
+    pub fn build(self) -> UrlPackage {
+        let mut module = Module::new();
+
+        register_groups(&mut module, &self.groups, self.file_paths);
+
+        // scripts register their schemes in a registry local to the package
+        let mut options = self.options;
+        if options.script_schemes {
+            options.schemes = options.schemes.extend();
+        }
+
+        // the exported functions use the default options, override the configurable ones
+        let options = Shared::new(options);
+        register_options(&mut module, &self.groups, options.clone());
+
+        // registered last, to replace `hash`
+        if self.web_compat && self.groups.contains(&FunctionGroup::Core) {
+            register_web_compat(&mut module, &options);
+        }
+
+        module.build_index();
+        UrlPackage(module.into())
+    }
+}
+
+/// Registers the exported functions of the given groups.
+#[allow(unused_variables)]
+fn register_groups(lib: &mut Module, groups: &BTreeSet<FunctionGroup>, file_paths: bool) {
+    // friendly type names for `type_of` and error messages, also when registered as a module
+    lib.set_custom_type::<Url>("Url")
+        .set_custom_type::<url::Origin>("Origin")
+        .set_custom_type::<crate::iter::QueryPair>("QueryPair")
+        .set_custom_type::<crate::iter::QueryPairs>("QueryPairs")
+        .set_custom_type::<crate::iter::QueryParams>("QueryParams")
+        .set_custom_type::<crate::iter::PathSegments>("PathSegments");
+
+    if groups.contains(&FunctionGroup::Core) {
+        combine_with_exported_module!(lib, "rhai_url", crate::url::url_module);
+        combine_with_exported_module!(lib, "rhai_url_origin", crate::origin::origin_module);
+
+        #[cfg(any(
+            unix,
+            windows,
+            target_os = "redox",
+            target_os = "wasi",
+            target_os = "hermit"
+        ))]
+        if file_paths {
+            combine_with_exported_module!(lib, "rhai_url_file", crate::file::file_module);
+        }
+    }
+
+    if groups.contains(&FunctionGroup::Query) {
+        combine_with_exported_module!(lib, "rhai_url_query", crate::query::query_module);
+        lib.set_iterable::<crate::iter::QueryPairs>();
+        lib.set_iterable::<crate::iter::QueryParams>();
+    }
+
+    if groups.contains(&FunctionGroup::Path) {
+        combine_with_exported_module!(lib, "rhai_url_path", crate::path::path_module);
+        lib.set_iterable::<crate::iter::PathSegments>();
+    }
+
+    #[cfg(feature = "array")]
+    if groups.contains(&FunctionGroup::Encoding) {
+        combine_with_exported_module!(lib, "rhai_url_nested", crate::nested::nested_module);
+    }
+
+    #[cfg(feature = "array")]
+    if groups.contains(&FunctionGroup::Batch) {
+        combine_with_exported_module!(lib, "rhai_url_batch", crate::batch::batch_module);
+    }
+
+    #[cfg(feature = "array")]
+    if groups.contains(&FunctionGroup::Connection) {
+        combine_with_exported_module!(
+            lib,
+            "rhai_url_connection",
+            crate::connection::connection_module
+        );
+    }
+}
+
+/// Replaces the functions depending on the options with closures capturing them.
+fn register_options(
+    lib: &mut Module,
+    groups: &BTreeSet<FunctionGroup>,
+    options: Shared<UrlOptions>,
+) {
+    if groups.contains(&FunctionGroup::Core) {
+        let opts = options.clone();
+        FuncRegistration::new("Url").set_into_module(lib, move |url: &str| opts.parse(url));
+
+        let opts = options.clone();
+        FuncRegistration::new_setter("scheme").set_into_module(
+            lib,
+            move |url: &mut Url, value: &str| -> Result<(), Box<EvalAltResult>> {
+                let mut changed = url.clone();
+                opts.set_scheme(&mut changed, value)?;
+                opts.check_limits(&changed)?;
+                *url = changed;
+                Ok(())
+            },
+        );
+
+        let opts = options.clone();
+        FuncRegistration::new("with_scheme")
+            .in_global_namespace()
+            .set_into_module(
+                lib,
+                move |url: &mut Url, scheme: &str| -> Result<Url, Box<EvalAltResult>> {
+                    opts.check_scheme(scheme)?;
+                    let mut url = crate::url::url_module::with_scheme(url, scheme)?;
+                    opts.schemes.normalize(&mut url);
+                    opts.check_limits(&url)?;
+                    Ok(url)
+                },
+            );
+
+        let opts = options.clone();
+        FuncRegistration::new("with_port")
+            .in_global_namespace()
+            .set_into_module(
+                lib,
+                move |url: &mut Url, port: rhai::INT| -> Result<Url, Box<EvalAltResult>> {
+                    let mut url = crate::url::url_module::with_port(url, port)?;
+                    opts.schemes.normalize(&mut url);
+                    opts.check_limits(&url)?;
+                    Ok(url)
+                },
+            );
+
+        use crate::origin::origin_module::{is_same_origin, is_same_site};
+
+        let opts = options.clone();
+        FuncRegistration::new("Origin").set_into_module(
+            lib,
+            move |url: &str| -> Result<url::Origin, Box<EvalAltResult>> {
+                Ok(opts.parse(url)?.origin())
+            },
+        );
+
+        let opts = options.clone();
+        FuncRegistration::new("is_same_origin")
+            .in_global_namespace()
+            .set_into_module(
+                lib,
+                move |url: &mut Url, other: &str| -> Result<bool, Box<EvalAltResult>> {
+                    Ok(is_same_origin(url, opts.parse(other)?))
+                },
+            );
+
+        let opts = options.clone();
+        FuncRegistration::new("is_same_site")
+            .in_global_namespace()
+            .set_into_module(
+                lib,
+                move |url: &mut Url, other: &str| -> Result<bool, Box<EvalAltResult>> {
+                    Ok(is_same_site(url, opts.parse(other)?))
+                },
+            );
+
+        if let Some(redact) = options.redact_debug.clone() {
+            FuncRegistration::new("to_debug")
+                .in_global_namespace()
+                .set_into_module(lib, move |url: &mut Url| -> ImmutableString {
+                    redact.redact(url).to_string().into()
+                });
+        }
+
+        if options.absent_as_unit {
+            let unit_or = |value: Option<&str>| {
+                value.map_or(Dynamic::UNIT, |v| ImmutableString::from(v).into())
+            };
+
+            FuncRegistration::new_getter("domain")
+                .set_into_module(lib, move |url: &mut Url| unit_or(url.domain()));
+            FuncRegistration::new_getter("query")
+                .set_into_module(lib, move |url: &mut Url| unit_or(url.query()));
+            FuncRegistration::new_getter("fragment")
+                .set_into_module(lib, move |url: &mut Url| unit_or(url.fragment()));
+            FuncRegistration::new_getter("hash")
+                .set_into_module(lib, move |url: &mut Url| unit_or(url.fragment()));
+        }
+
+        crate::scheme::register_functions(lib, &options.schemes, options.script_schemes);
+    }
+
+    #[cfg(feature = "array")]
+    if groups.contains(&FunctionGroup::Connection) {
+        use crate::connection::parse_connection;
+
+        let opts = options.clone();
+        FuncRegistration::new("connection_info")
+            .in_global_namespace()
+            .set_into_module(lib, move |url: &mut Url| {
+                parse_connection(&opts.schemes, url.as_str())
+            });
+
+        let opts = options.clone();
+        FuncRegistration::new("connection_info")
+            .set_into_module(lib, move |url: &str| parse_connection(&opts.schemes, url));
+    }
+
+    #[cfg(feature = "array")]
+    if groups.contains(&FunctionGroup::Batch) {
+        use crate::batch::{self, NormalizeOptions};
+        use rhai::{Array, Map};
+
+        let opts = options.clone();
+        FuncRegistration::new("parse_urls")
+            .set_into_module(lib, move |inputs: Array| batch::parse_urls(&opts, inputs));
+
+        let opts = options.clone();
+        FuncRegistration::new("urls_join").set_into_module(
+            lib,
+            move |base: Dynamic, inputs: Array| -> Result<Array, Box<EvalAltResult>> {
+                let base = opts.to_url(&base)?;
+                Ok(batch::urls_join(&opts, &base, inputs))
+            },
+        );
+
+        let opts = options.clone();
+        FuncRegistration::new("urls_normalize").set_into_module(lib, move |inputs: Array| {
+            batch::urls_normalize(&opts, inputs, &NormalizeOptions::default())
+        });
+
+        let opts = options.clone();
+        FuncRegistration::new("urls_normalize").set_into_module(
+            lib,
+            move |inputs: Array, map: Map| -> Result<Array, Box<EvalAltResult>> {
+                let normalize = NormalizeOptions::from_map(&map)?;
+                Ok(batch::urls_normalize(&opts, inputs, &normalize))
+            },
+        );
+
+        let opts = options.clone();
+        FuncRegistration::new("urls_dedupe")
+            .set_into_module(lib, move |inputs: Array| batch::urls_dedupe(&opts, inputs));
+
+        let opts = options.clone();
+        FuncRegistration::new("urls_group_by_host").set_into_module(lib, move |inputs: Array| {
+            batch::urls_group_by_host(&opts, inputs)
+        });
+
+        let opts = options.clone();
+        FuncRegistration::new("urls_filter_by_pattern").set_into_module(
+            lib,
+            move |inputs: Array, pattern: &str| {
+                batch::urls_filter_by_pattern(&opts, inputs, pattern)
+            },
+        );
+    }
+
+    if groups.contains(&FunctionGroup::Core) {
+        register_string_overloads(lib, groups, &options);
+    }
+
+    if options.has_limits() {
+        register_limits(lib, groups, &options);
+    }
+}
+
+/// Wraps a function taking a Url into an overload taking a string, which is parsed
+/// with the options and passed to the registered Url function.
+macro_rules! parsed {
+    ($options:expr, $name:expr $(, $arg:ident: $type:ty)*) => {{
+        let opts = $options.clone();
+        move |ctx: NativeCallContext,
+              url: &mut ImmutableString
+              $(, $arg: $type)*|
+              -> Result<Dynamic, Box<EvalAltResult>> {
+            let url = opts.parse(url)?;
+            ctx.call_native_fn($name, (url, $($arg,)*))
+        }
+    }};
+}
+
+/// Registers a string overload of every non-mutating function of the Url and origin modules,
+/// and of the query and path functions of the registered groups.
+///
+/// Setters are left out, as is `to_string`, which strings already have.
+fn register_string_overloads(
+    lib: &mut Module,
+    groups: &BTreeSet<FunctionGroup>,
+    options: &Shared<UrlOptions>,
+) {
+    for getter in [
+        "href",
+        "scheme",
+        "domain",
+        "has_host",
+        "has_port",
+        "has_credentials",
+        "cannot_be_a_base",
+        "path",
+        "query",
+        "has_query",
+        "fragment",
+        "has_fragment",
+        "hash",
+        "effective_port",
+        "origin",
+        "is_tuple_origin",
+        "authority",
+        "base",
+        "without_fragment",
+    ] {
+        let name = format!("get${getter}");
+        FuncRegistration::new_getter(getter).set_into_module(lib, parsed!(options, &name));
+    }
+
+    let global = |name: &str| FuncRegistration::new(name).in_global_namespace();
+
+    for name in [
+        "with_scheme",
+        "with_host",
+        "with_path",
+        "with_query",
+        "with_fragment",
+    ] {
+        global(name).set_into_module(lib, parsed!(options, name, value: ImmutableString));
+    }
+    for name in ["with_host", "with_query", "with_fragment"] {
+        global(name).set_into_module(lib, parsed!(options, name, value: ()));
+    }
+    global("with_port").set_into_module(lib, parsed!(options, "with_port", port: rhai::INT));
+    global("with_port").set_into_module(lib, parsed!(options, "with_port", port: ()));
+    global("without_fragment").set_into_module(lib, parsed!(options, "without_fragment"));
+    global("slice").set_into_module(
+        lib,
+        parsed!(options, "slice", from: ImmutableString, to: ImmutableString),
+    );
+    global("with_query_param").set_into_module(
+        lib,
+        parsed!(options, "with_query_param", key: ImmutableString, value: Dynamic),
+    );
+    global("without_query_param").set_into_module(
+        lib,
+        parsed!(options, "without_query_param", key: ImmutableString),
+    );
+    for name in ["is_same_origin", "is_same_site"] {
+        global(name).set_into_module(lib, parsed!(options, name, other: Url));
+        global(name).set_into_module(lib, parsed!(options, name, other: ImmutableString));
+    }
+    global("redacted").set_into_module(lib, parsed!(options, "redacted"));
+    global("redacted").set_into_module(lib, parsed!(options, "redacted", map: rhai::Map));
+    global("to_safe_string").set_into_module(lib, parsed!(options, "to_safe_string"));
+
+    if groups.contains(&FunctionGroup::Query) {
+        for name in [
+            "query_get",
+            "query_get_int",
+            "query_get_float",
+            "query_get_bool",
+            #[cfg(feature = "array")]
+            "query_gets",
+            #[cfg(feature = "array")]
+            "query_getAll",
+        ] {
+            global(name).set_into_module(lib, parsed!(options, name, key: ImmutableString));
+        }
+        global("query_pairs").set_into_module(lib, parsed!(options, "query_pairs"));
+        FuncRegistration::new_getter("params").set_into_module(lib, parsed!(options, "get$params"));
+    }
+
+    if groups.contains(&FunctionGroup::Path) {
+        global("path_segments").set_into_module(lib, parsed!(options, "path_segments"));
+    }
+
+    #[cfg(feature = "array")]
+    if groups.contains(&FunctionGroup::Encoding) {
+        FuncRegistration::new_getter("query_nested")
+            .set_into_module(lib, parsed!(options, "get$query_nested"));
+    }
+}
+
+/// Registers the browser `URL` properties, with setters honoring the options.
+fn register_web_compat(lib: &mut Module, options: &Shared<UrlOptions>) {
+    combine_with_exported_module!(lib, "rhai_url_web", crate::web::web_module);
+
+    let opts = options.clone();
+    FuncRegistration::new_setter("href").set_into_module(
+        lib,
+        move |url: &mut Url, value: &str| -> Result<(), Box<EvalAltResult>> {
+            *url = opts.parse(value)?;
+            Ok(())
+        },
+    );
+
+    for (property, _) in crate::web::SETTERS {
+        let opts = options.clone();
+        FuncRegistration::new_setter(property)
+            .set_into_module(lib, move |url: &mut Url, value: &str| {
+                crate::web::set(&opts, url, property, value)
+            });
+    }
+
+    for getter in [
+        "protocol", "username", "password", "host", "hostname", "port", "pathname", "search",
+    ] {
+        let name = format!("get${getter}");
+        FuncRegistration::new_getter(getter).set_into_module(lib, parsed!(options, &name));
+    }
+}
+
+/// Wraps a function mutating a Url so that the change is discarded
+/// with an error when the result exceeds the resource limits.
+macro_rules! limited {
+    ($options:expr, $func:path $(, $arg:ident: $type:ty)*) => {{
+        let opts = $options.clone();
+        move |url: &mut Url $(, $arg: $type)*| -> Result<(), Box<EvalAltResult>> {
+            let mut changed = url.clone();
+            $func(&mut changed $(, $arg)*);
+            opts.check_limits(&changed)?;
+            *url = changed;
+            Ok(())
+        }
+    }};
+    ($options:expr, $func:path $(, $arg:ident: $type:ty)*; fallible) => {{
+        let opts = $options.clone();
+        move |url: &mut Url $(, $arg: $type)*| -> Result<(), Box<EvalAltResult>> {
+            let mut changed = url.clone();
+            $func(&mut changed $(, $arg)*)?;
+            opts.check_limits(&changed)?;
+            *url = changed;
+            Ok(())
+        }
+    }};
+    ($options:expr, $func:path $(, $arg:ident: $type:ty)*; returns) => {{
+        let opts = $options.clone();
+        move |url: &mut Url $(, $arg: $type)*| -> Result<Url, Box<EvalAltResult>> {
+            let url = $func(url $(, $arg)*);
+            opts.check_limits(&url)?;
+            Ok(url)
+        }
+    }};
+    ($options:expr, $func:path $(, $arg:ident: $type:ty)*; returns_fallible) => {{
+        let opts = $options.clone();
+        move |url: &mut Url $(, $arg: $type)*| -> Result<Url, Box<EvalAltResult>> {
+            let url = $func(url $(, $arg)*)?;
+            opts.check_limits(&url)?;
+            Ok(url)
+        }
+    }};
+}
+
+/// Replaces the functions that can grow a Url with versions enforcing the resource limits.
+fn register_limits(
+    lib: &mut Module,
+    groups: &BTreeSet<FunctionGroup>,
+    options: &Shared<UrlOptions>,
+) {
+    if groups.contains(&FunctionGroup::Core) {
+        use crate::url::url_module::*;
+
+        FuncRegistration::new_setter("path")
+            .set_into_module(lib, limited!(options, set_path, value: &str));
+        FuncRegistration::new_setter("query")
+            .set_into_module(lib, limited!(options, set_query, value: &str));
+        FuncRegistration::new_setter("fragment")
+            .set_into_module(lib, limited!(options, set_fragment, value: &str));
+        FuncRegistration::new_setter("hash")
+            .set_into_module(lib, limited!(options, set_hash, value: &str));
+
+        FuncRegistration::new("with_host")
+            .in_global_namespace()
+            .set_into_module(
+                lib,
+                limited!(options, with_host, host: &str; returns_fallible),
+            );
+        FuncRegistration::new("with_path")
+            .in_global_namespace()
+            .set_into_module(lib, limited!(options, with_path, path: &str; returns));
+        FuncRegistration::new("with_query")
+            .in_global_namespace()
+            .set_into_module(lib, limited!(options, with_query, query: &str; returns));
+        FuncRegistration::new("with_fragment")
+            .in_global_namespace()
+            .set_into_module(
+                lib,
+                limited!(options, with_fragment, fragment: &str; returns),
+            );
+        FuncRegistration::new("with_query_param")
+            .in_global_namespace()
+            .set_into_module(
+                lib,
+                limited!(options, with_query_param, key: &str, value: Dynamic; returns),
+            );
+    }
+
+    if groups.contains(&FunctionGroup::Query) {
+        use crate::query::query_module::*;
+
+        let global = |name: &str| FuncRegistration::new(name).in_global_namespace();
+
+        global("query_append")
+            .set_into_module(lib, limited!(options, query_append, key: &str, value: &str));
+        global("query_append").set_into_module(
+            lib,
+            limited!(options, query_append_dynamic, key: &str, value: Dynamic),
+        );
+        global("query_append").set_into_module(
+            lib,
+            limited!(options, query_append_format, key: &str, value: Dynamic, format: &str; fallible),
+        );
+        global("query_set")
+            .set_into_module(lib, limited!(options, query_set, key: &str, value: &str));
+        global("query_set").set_into_module(
+            lib,
+            limited!(options, query_set_dynamic, key: &str, value: Dynamic),
+        );
+        global("query_set").set_into_module(
+            lib,
+            limited!(options, query_set_format, key: &str, value: Dynamic, format: &str; fallible),
+        );
+        global("query_rename")
+            .set_into_module(lib, limited!(options, query_rename, from: &str, to: &str));
+        FuncRegistration::new_setter("params").set_into_module(
+            lib,
+            limited!(options, set_params, params: crate::iter::QueryParams),
+        );
+        global("query_merge").set_into_module(
+            lib,
+            limited!(options, query_merge, map: rhai::Map, overwrite: bool),
+        );
+    }
+
+    #[cfg(feature = "array")]
+    if groups.contains(&FunctionGroup::Encoding) {
+        FuncRegistration::new_setter("query_nested").set_into_module(
+            lib,
+            limited!(options, crate::nested::nested_module::set_query_nested, value: rhai::Map),
+        );
+    }
+}
@@ -0,0 +1,29 @@
+#[allow(unused_imports)]
+use rhai::plugin::*;
+
+#[export_module]
+pub mod path_module {
+    use crate::iter::PathSegments;
+    use url::Url;
+
+    /// Gets an iterable over the path segments, no array is allocated.
+    ///
+    /// Segments are percent-encoded, cannot-be-a-base Urls yield no segments.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// let url = Url("http://test.dev/a/b");
+    /// let segments = [];
+    ///
+    /// for segment in url.path_segments() {
+    ///     segments.push(segment);
+    /// }
+    ///
+    /// segments; // ["a", "b"]
+    /// ```
+    #[rhai_fn(global, pure)]
+    pub fn path_segments(url: &mut Url) -> PathSegments {
+        PathSegments::new(url)
+    }
+}
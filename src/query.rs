@@ -0,0 +1,895 @@
+#[allow(unused_imports)]
+use rhai::plugin::*;
+
+use std::{borrow::Cow, convert::Infallible};
+use url::{form_urlencoded, Url};
+
+/// How array values are written into a query string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ArrayFormat {
+    /// Repeated keys: `list=x&list=y`.
+    #[default]
+    Repeat,
+    /// Empty brackets: `list[]=x&list[]=y`.
+    Brackets,
+    /// Indexed brackets: `list[0]=x&list[1]=y`.
+    Indices,
+    /// A single comma separated value: `list=x,y`.
+    Comma,
+}
+
+impl ArrayFormat {
+    /// Parses an array format name, one of `repeat`, `brackets`, `indices` or `comma`.
+    pub fn parse(name: &str) -> Result<Self, Box<EvalAltResult>> {
+        match name {
+            "repeat" => Ok(Self::Repeat),
+            "brackets" => Ok(Self::Brackets),
+            "indices" => Ok(Self::Indices),
+            "comma" => Ok(Self::Comma),
+            _ => Err(format!(
+                "unknown array format '{name}', expected one of 'repeat', 'brackets', 'indices' or 'comma'"
+            )
+            .into()),
+        }
+    }
+}
+
+/// Flattens a value into query pairs under the given key.
+///
+/// Scalars are converted to strings, arrays are expanded according to `format`
+/// and maps are flattened using the bracket notation, `()` produces no pairs.
+#[cfg_attr(not(feature = "array"), allow(clippy::only_used_in_recursion))]
+pub fn flatten_pairs(
+    key: &str,
+    value: &Dynamic,
+    format: ArrayFormat,
+    pairs: &mut Vec<(String, String)>,
+) {
+    if value.is_unit() {
+        return;
+    }
+
+    #[cfg(feature = "array")]
+    if let Ok(array) = value.as_array_ref() {
+        match format {
+            ArrayFormat::Repeat => {
+                for item in array.iter() {
+                    flatten_pairs(key, item, format, pairs);
+                }
+            }
+            ArrayFormat::Brackets => {
+                let key = format!("{key}[]");
+                for item in array.iter() {
+                    flatten_pairs(&key, item, format, pairs);
+                }
+            }
+            ArrayFormat::Indices => {
+                for (index, item) in array.iter().enumerate() {
+                    flatten_pairs(&format!("{key}[{index}]"), item, format, pairs);
+                }
+            }
+            ArrayFormat::Comma => {
+                let joined = array
+                    .iter()
+                    .filter(|item| !item.is_unit())
+                    .map(|item| item.to_string())
+                    .collect::<Vec<_>>()
+                    .join(",");
+                pairs.push((key.to_string(), joined));
+            }
+        }
+        return;
+    }
+
+    if let Ok(map) = value.as_map_ref() {
+        for (name, item) in map.iter() {
+            flatten_pairs(&format!("{key}[{name}]"), item, format, pairs);
+        }
+        return;
+    }
+
+    pairs.push((key.to_string(), value.to_string()));
+}
+
+/// Returns `true` if the query key is `key` itself or one of its bracketed forms, such as `key[]`.
+pub fn is_key_or_nested(name: &str, key: &str) -> bool {
+    name == key || (name.starts_with(key) && name[key.len()..].starts_with('['))
+}
+
+/// Gets the first decoded value for the specified key.
+pub fn first_value(url: &Url, key: &str) -> Option<String> {
+    url.query_pairs()
+        .find(|(name, _)| name == key)
+        .map(|(_, value)| value.into_owned())
+}
+
+/// Appends the pairs of a flattened value.
+pub fn append_pairs(url: &mut Url, key: &str, value: &Dynamic, format: ArrayFormat) {
+    let mut pairs = Vec::new();
+    flatten_pairs(key, value, format, &mut pairs);
+
+    if !pairs.is_empty() {
+        url.query_pairs_mut().extend_pairs(&pairs);
+    }
+}
+
+/// Splits a raw query into its `key=value` segments, as they appear in the Url.
+///
+/// Empty segments such as in `a=1&&b=2` are skipped, like the parser does.
+pub fn raw_pairs(query: &str) -> impl DoubleEndedIterator<Item = &str> {
+    query.split('&').filter(|segment| !segment.is_empty())
+}
+
+/// Gets the key of a raw segment, still encoded.
+pub fn encoded_key(segment: &str) -> &str {
+    segment.split_once('=').map_or(segment, |(key, _)| key)
+}
+
+/// Decodes the key of a raw segment, borrowed when there is nothing to decode.
+pub fn raw_key(segment: &str) -> Cow<'_, str> {
+    form_urlencoded::parse(encoded_key(segment).as_bytes())
+        .next()
+        .map_or(Cow::Borrowed(""), |(key, _)| key)
+}
+
+/// Decodes the key and the value of a raw segment.
+pub fn raw_pair(segment: &str) -> (Cow<'_, str>, Cow<'_, str>) {
+    form_urlencoded::parse(segment.as_bytes())
+        .next()
+        .unwrap_or_default()
+}
+
+/// No pairs to append, for the edits that only drop pairs.
+pub const NO_PAIRS: &[(&str, &str)] = &[];
+
+/// Appends a raw segment to a query being built, unchanged.
+fn push_raw(query: &mut String, segment: &str) {
+    if !query.is_empty() {
+        query.push('&');
+    }
+    query.push_str(segment);
+}
+
+/// Appends an encoded pair to a query being built, as `Url::query_pairs_mut` does.
+fn push_pair(query: &mut String, key: &str, value: &str) {
+    if !query.is_empty() {
+        query.push('&');
+    }
+    query.extend(form_urlencoded::byte_serialize(key.as_bytes()));
+    query.push('=');
+    query.extend(form_urlencoded::byte_serialize(value.as_bytes()));
+}
+
+/// Replaces the query with a raw query, removing the '?' when it is empty.
+fn set_raw_query(url: &mut Url, query: &str) {
+    url.set_query(Some(query).filter(|query| !query.is_empty()));
+}
+
+/// Edits the query in a single pass, the Url is left untouched when nothing changes.
+///
+/// The segments for which `keep` returns `true` are copied byte for byte and the others
+/// are dropped, without decoding their values, then `pairs` are appended encoded.
+/// Empty segments, such as in `a=1&&b=2`, and a bare '?' are kept as they are unless a pair
+/// is dropped or appended; the rewritten query has no empty segment, and no '?' when no pair
+/// is left.
+pub fn try_retain_pairs<E>(
+    url: &mut Url,
+    mut keep: impl FnMut(&str) -> Result<bool, E>,
+    pairs: &[(impl AsRef<str>, impl AsRef<str>)],
+) -> Result<(), E> {
+    let Some(original) = url.query() else {
+        if !pairs.is_empty() {
+            url.query_pairs_mut().extend_pairs(pairs);
+        }
+        return Ok(());
+    };
+
+    let mut query = String::with_capacity(original.len());
+    let mut changed = !pairs.is_empty();
+
+    for segment in raw_pairs(original) {
+        match keep(segment)? {
+            true => push_raw(&mut query, segment),
+            false => changed = true,
+        }
+    }
+
+    if changed {
+        for (key, value) in pairs {
+            push_pair(&mut query, key.as_ref(), value.as_ref());
+        }
+        set_raw_query(url, &query);
+    }
+
+    Ok(())
+}
+
+/// Infallible [`try_retain_pairs`].
+pub fn retain_pairs(
+    url: &mut Url,
+    mut keep: impl FnMut(&str) -> bool,
+    pairs: &[(impl AsRef<str>, impl AsRef<str>)],
+) {
+    match try_retain_pairs(url, |segment| Ok::<_, Infallible>(keep(segment)), pairs) {
+        Ok(()) => {}
+        Err(never) => match never {},
+    }
+}
+
+/// Removes every pair with the given key, the other pairs keep their encoding.
+pub fn delete_pairs(url: &mut Url, key: &str) {
+    retain_pairs(url, |segment| raw_key(segment) != key, NO_PAIRS);
+}
+
+/// Replaces a key and its bracketed forms with the pairs of a flattened value.
+pub fn set_pairs(url: &mut Url, key: &str, value: &Dynamic, format: ArrayFormat) {
+    let mut pairs = Vec::new();
+    flatten_pairs(key, value, format, &mut pairs);
+
+    retain_pairs(
+        url,
+        |segment| !is_key_or_nested(&raw_key(segment), key),
+        &pairs,
+    );
+}
+
+/// Sorts the query pairs by decoded key, pairs with the same key keep their order.
+pub fn sort_pairs(url: &mut Url) {
+    let Some(query) = url.query() else {
+        return;
+    };
+
+    let mut segments: Vec<_> = raw_pairs(query)
+        .map(|segment| (raw_key(segment), segment))
+        .collect();
+    // the sort is stable, sorted pairs would be written back as they are
+    if segments.windows(2).all(|pair| pair[0].0 <= pair[1].0) {
+        return;
+    }
+    segments.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let mut sorted = String::with_capacity(query.len());
+    for (_, segment) in segments {
+        push_raw(&mut sorted, segment);
+    }
+    set_raw_query(url, &sorted);
+}
+
+#[export_module]
+pub mod query_module {
+    use super::{
+        append_pairs, delete_pairs, encoded_key, first_value, flatten_pairs, is_key_or_nested,
+        raw_key, raw_pair, raw_pairs, retain_pairs, set_pairs, sort_pairs, try_retain_pairs,
+        ArrayFormat, NO_PAIRS,
+    };
+    use crate::iter::{QueryPair, QueryPairs, QueryParams};
+    use rhai::{FnPtr, Map};
+    use std::collections::HashSet;
+    use url::Url;
+
+    #[rhai_fn(
+        global,
+        name = "query_clear",
+        name = "query_delete",
+        name = "query_remove",
+        pure
+    )]
+    /// Clear the query string.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// let url = Url("http://test.dev/?q=query&b=1");
+    ///
+    /// url.query_clear();
+    ///
+    /// url == "http://test.dev/"
+    /// ```
+    pub fn query_clear(url: &mut Url) {
+        url.set_query(None)
+    }
+
+    /// Delete a key from the query
+    ///
+    /// The other pairs are left as they are, with their original encoding.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// let url = Url("http://test.dev/?q=query&b=1&c=2");
+    ///
+    /// url.query_delete("q");
+    /// // query_remove is an alias
+    /// url.query_remove("c");
+    ///
+    /// url == "http://test.dev/?b=1"
+    /// ```
+    #[rhai_fn(global, name = "query_delete", name = "query_remove", pure)]
+    pub fn query_delete(url: &mut Url, key: &str) {
+        delete_pairs(url, key);
+    }
+
+    /// Appends a query key value pair, existing keys are preserved
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// let url = Url("http://test.dev/?q=query");
+    ///
+    /// url.query_append("q", "second-query");
+    ///
+    /// url == "http://test.dev/?q=query&q=second-query"
+    /// ```
+    #[rhai_fn(global, name = "query_append", pure)]
+    pub fn query_append(url: &mut Url, key: &str, value: &str) {
+        url.query_pairs_mut().append_pair(key, value);
+    }
+
+    /// Sets a query key
+    ///
+    /// The key and any of its bracketed forms, such as `key[]`, are replaced like with the
+    /// other `query_set` overloads. The key is moved to the end of the query, the other pairs
+    /// keep their original encoding.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// let url = Url("http://test.dev/?q=query&b=1");
+    ///
+    /// url.query_set("q", "new-query");
+    ///
+    /// url == "http://test.dev/?b=1&q=new-query"
+    /// ```
+    #[rhai_fn(global, name = "query_set", pure)]
+    pub fn query_set(url: &mut Url, key: &str, value: &str) {
+        retain_pairs(
+            url,
+            |segment| !is_key_or_nested(&raw_key(segment), key),
+            &[(key, value)],
+        );
+    }
+
+    /// Gets a query value for the specified key, it will return the first value found
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// let url = Url("http://test.dev/?q=query&b=1");
+    ///
+    /// url.query_get("q"); // "query"
+    /// url.query_get("b"); // "1"
+    /// ```
+    #[rhai_fn(global, name = "query_get", pure)]
+    pub fn query_get(url: &mut Url, key: &str) -> ImmutableString {
+        match url.query_pairs().find(|(name, _)| name == key) {
+            Some((_, value)) => ImmutableString::from(value.as_ref()),
+            None => ImmutableString::from(""),
+        }
+    }
+
+    /// Gets a list of values for the specified key
+    ///
+    /// Not available under `no_index`.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// let url = Url("http://test.dev/?q=query&q=second-query");
+    ///
+    /// let values = url.query_gets("q"); // ["query", "second-query"]
+    ///
+    /// values.len() == 2
+    /// ```
+    #[cfg(feature = "array")]
+    #[rhai_fn(global, name = "query_gets", name = "query_getAll", pure)]
+    pub fn query_gets(url: &mut Url, key: &str) -> rhai::Array {
+        url.query_pairs()
+            .filter(|(name, _)| name == key)
+            .map(|(_, value)| ImmutableString::from(value.as_ref()).into())
+            .collect()
+    }
+
+    /// Gets the decoded key of a query pair.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// let key = "";
+    ///
+    /// for pair in Url("http://test.dev/?q=query").query_pairs() {
+    ///     key = pair.key;
+    /// }
+    ///
+    /// key; // 'q'
+    /// ```
+    #[rhai_fn(global, get = "key", pure)]
+    pub fn key(pair: &mut QueryPair) -> ImmutableString {
+        pair.key.clone()
+    }
+
+    /// Gets the decoded value of a query pair.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// let value = "";
+    ///
+    /// for pair in Url("http://test.dev/?q=query").query_pairs() {
+    ///     value = pair.value;
+    /// }
+    ///
+    /// value; // 'query'
+    /// ```
+    #[rhai_fn(global, get = "value", pure)]
+    pub fn value(pair: &mut QueryPair) -> ImmutableString {
+        pair.value.clone()
+    }
+
+    /// Get the query pair as a `key=value` string, without any encoding.
+    #[rhai_fn(global, name = "to_string", name = "to_debug", pure)]
+    pub fn pair_to_string(pair: &mut QueryPair) -> ImmutableString {
+        format!("{}={}", pair.key, pair.value).into()
+    }
+
+    /// Gets an iterable over the decoded query pairs, no array is allocated.
+    ///
+    /// The loop form is `for pair in url.query_pairs() { pair.key; pair.value }`.
+    /// Rhai binds a second loop variable to the iteration counter, so in
+    /// `for (k, v) in url.query_pairs()` the `k` is the whole pair and `v` is `0, 1, …`,
+    /// not the value.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// let url = Url("http://test.dev/?q=query&b=1");
+    ///
+    /// for pair in url.query_pairs() {
+    ///     print(pair.key + " = " + pair.value);
+    /// }
+    /// ```
+    #[rhai_fn(global, pure)]
+    pub fn query_pairs(url: &mut Url) -> QueryPairs {
+        QueryPairs::new(url)
+    }
+
+    /// Gets the query parameters, read and written with the indexer.
+    ///
+    /// Reading a key returns its first value, or `()` when missing. Writing a key replaces
+    /// all of its values like `query_set`, writing `()` removes it. The parameters can also
+    /// be iterated as pairs.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// let url = Url("http://test.dev/?page=1");
+    ///
+    /// let page = url.params["page"]; // '1'
+    /// url.params["page"] = 2;
+    /// url.params["sort"] = "name";
+    ///
+    /// url == "http://test.dev/?page=2&sort=name"
+    /// ```
+    #[rhai_fn(global, get = "params", pure)]
+    pub fn params(url: &mut Url) -> QueryParams {
+        QueryParams::new(url)
+    }
+
+    /// Sets the query from query parameters, see the `params` getter.
+    #[rhai_fn(global, set = "params")]
+    pub fn set_params(url: &mut Url, params: QueryParams) {
+        url.set_query(params.url.query());
+    }
+
+    /// Gets the first value of a query parameter, or `()` when missing.
+    #[rhai_fn(global, index_get, pure)]
+    pub fn params_get(params: &mut QueryParams, key: &str) -> Dynamic {
+        first_value(&params.url, key).map_or(Dynamic::UNIT, Into::into)
+    }
+
+    /// Sets a query parameter, replacing its values, `()` removes it.
+    #[rhai_fn(global, index_set)]
+    pub fn params_set(params: &mut QueryParams, key: &str, value: Dynamic) {
+        set_pairs(&mut params.url, key, &value, ArrayFormat::Repeat);
+    }
+
+    /// Get the query parameters as a query string.
+    #[rhai_fn(global, name = "to_string", name = "to_debug", pure)]
+    pub fn params_to_string(params: &mut QueryParams) -> ImmutableString {
+        params.url.query().unwrap_or("").into()
+    }
+
+    /// Appends a value to the query, converting it into one or more pairs.
+    ///
+    /// Numbers and booleans are converted to strings, arrays repeat the key
+    /// and maps are flattened using the bracket notation, `()` appends nothing.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// let url = Url("http://test.dev/");
+    ///
+    /// url.query_append("page", 2);
+    /// url.query_append("tag", ["a", "b"]);
+    /// url.query_append("filter", #{ status: "open" });
+    ///
+    /// url == "http://test.dev/?page=2&tag=a&tag=b&filter%5Bstatus%5D=open"
+    /// ```
+    #[rhai_fn(global, name = "query_append", pure)]
+    pub fn query_append_dynamic(url: &mut Url, key: &str, value: Dynamic) {
+        append_pairs(url, key, &value, ArrayFormat::Repeat);
+    }
+
+    /// Appends a value to the query, writing arrays with the given format.
+    ///
+    /// The format is one of `repeat`, `brackets`, `indices` or `comma`.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// let url = Url("http://test.dev/");
+    ///
+    /// url.query_append("tag", ["a", "b"], "brackets");
+    ///
+    /// url == "http://test.dev/?tag%5B%5D=a&tag%5B%5D=b"
+    /// ```
+    #[rhai_fn(global, name = "query_append", pure, return_raw)]
+    pub fn query_append_format(
+        url: &mut Url,
+        key: &str,
+        value: Dynamic,
+        format: &str,
+    ) -> Result<(), Box<EvalAltResult>> {
+        append_pairs(url, key, &value, ArrayFormat::parse(format)?);
+        Ok(())
+    }
+
+    /// Sets a query key to a value, converting it into one or more pairs.
+    ///
+    /// The key and any of its bracketed forms, such as `key[]`, are replaced,
+    /// setting `()` removes them.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// let url = Url("http://test.dev/?page=1&b=1");
+    ///
+    /// url.query_set("page", 2);
+    ///
+    /// url == "http://test.dev/?b=1&page=2"
+    /// ```
+    #[rhai_fn(global, name = "query_set", pure)]
+    pub fn query_set_dynamic(url: &mut Url, key: &str, value: Dynamic) {
+        set_pairs(url, key, &value, ArrayFormat::Repeat);
+    }
+
+    /// Sets a query key to a value, writing arrays with the given format.
+    ///
+    /// The format is one of `repeat`, `brackets`, `indices` or `comma`.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// let url = Url("http://test.dev/?tag=a");
+    ///
+    /// url.query_set("tag", ["b", "c"], "comma");
+    ///
+    /// url == "http://test.dev/?tag=b%2Cc"
+    /// ```
+    #[rhai_fn(global, name = "query_set", pure, return_raw)]
+    pub fn query_set_format(
+        url: &mut Url,
+        key: &str,
+        value: Dynamic,
+        format: &str,
+    ) -> Result<(), Box<EvalAltResult>> {
+        set_pairs(url, key, &value, ArrayFormat::parse(format)?);
+        Ok(())
+    }
+
+    /// Gets a query value as an integer, `()` when missing or not an integer.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// let url = Url("http://test.dev/?page=2&q=query");
+    ///
+    /// url.query_get_int("page"); // 2
+    /// url.query_get_int("q"); // ()
+    /// ```
+    #[rhai_fn(global, pure)]
+    pub fn query_get_int(url: &mut Url, key: &str) -> Dynamic {
+        first_value(url, key)
+            .and_then(|value| value.trim().parse::<rhai::INT>().ok())
+            .map_or(Dynamic::UNIT, Dynamic::from)
+    }
+
+    /// Gets a query value as a float, `()` when missing or not a number.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// let url = Url("http://test.dev/?lat=45.5");
+    ///
+    /// url.query_get_float("lat"); // 45.5
+    /// ```
+    #[rhai_fn(global, pure)]
+    pub fn query_get_float(url: &mut Url, key: &str) -> Dynamic {
+        first_value(url, key)
+            .and_then(|value| value.trim().parse::<rhai::FLOAT>().ok())
+            .map_or(Dynamic::UNIT, Dynamic::from)
+    }
+
+    /// Gets a query value as a boolean, `()` when missing or not a boolean.
+    ///
+    /// `true`, `1`, `yes` and `on` are true, `false`, `0`, `no` and `off` are false.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// let url = Url("http://test.dev/?debug=1&dry=false");
+    ///
+    /// url.query_get_bool("debug"); // true
+    /// url.query_get_bool("dry"); // false
+    /// ```
+    #[rhai_fn(global, pure)]
+    pub fn query_get_bool(url: &mut Url, key: &str) -> Dynamic {
+        match first_value(url, key).as_deref().map(str::trim) {
+            Some("true" | "1" | "yes" | "on") => Dynamic::TRUE,
+            Some("false" | "0" | "no" | "off") => Dynamic::FALSE,
+            _ => Dynamic::UNIT,
+        }
+    }
+
+    /*************************************************************
+     * Bulk operations
+     ************************************************************/
+
+    /// Keeps only the query pairs for which the function returns `true`.
+    ///
+    /// The function is called with the decoded key and value.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// let url = Url("http://test.dev/?utm_source=x&q=query&utm_medium=y");
+    ///
+    /// url.query_retain(|key, value| !key.starts_with("utm_"));
+    ///
+    /// url == "http://test.dev/?q=query"
+    /// ```
+    #[rhai_fn(global, return_raw)]
+    pub fn query_retain(
+        ctx: NativeCallContext,
+        url: &mut Url,
+        filter: FnPtr,
+    ) -> Result<(), Box<EvalAltResult>> {
+        let keep = |segment: &str| {
+            let (name, value) = raw_pair(segment);
+            let args = (name.into_owned(), value.into_owned());
+            filter.call_within_context::<bool>(&ctx, args)
+        };
+
+        try_retain_pairs(url, keep, NO_PAIRS)
+    }
+
+    /// Removes the query pairs for which the function returns `true`.
+    ///
+    /// The function is called with the decoded key and value.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// let url = Url("http://test.dev/?a=&q=query&b=");
+    ///
+    /// url.query_remove_where(|key, value| value == "");
+    ///
+    /// url == "http://test.dev/?q=query"
+    /// ```
+    #[rhai_fn(global, return_raw)]
+    pub fn query_remove_where(
+        ctx: NativeCallContext,
+        url: &mut Url,
+        filter: FnPtr,
+    ) -> Result<(), Box<EvalAltResult>> {
+        let keep = |segment: &str| {
+            let (name, value) = raw_pair(segment);
+            let args = (name.into_owned(), value.into_owned());
+            filter
+                .call_within_context::<bool>(&ctx, args)
+                .map(|matched| !matched)
+        };
+
+        try_retain_pairs(url, keep, NO_PAIRS)
+    }
+
+    /// Renames a query key, keeping the position and value of every pair.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// let url = Url("http://test.dev/?q=query&b=1");
+    ///
+    /// url.query_rename("q", "search");
+    ///
+    /// url == "http://test.dev/?search=query&b=1"
+    /// ```
+    #[rhai_fn(global, pure)]
+    pub fn query_rename(url: &mut Url, from: &str, to: &str) {
+        let Some(query) = url.query() else {
+            return;
+        };
+
+        let mut renamed = String::with_capacity(query.len());
+        let mut changed = false;
+
+        for segment in raw_pairs(query) {
+            if !renamed.is_empty() {
+                renamed.push('&');
+            }
+            if raw_key(segment) == from {
+                // only the key is encoded again, the value keeps its encoding
+                renamed.extend(form_urlencoded::byte_serialize(to.as_bytes()));
+                renamed.push_str(&segment[encoded_key(segment).len()..]);
+                changed = true;
+            } else {
+                renamed.push_str(segment);
+            }
+        }
+
+        if changed {
+            url.set_query(Some(&renamed));
+        }
+    }
+
+    /// Sorts the query pairs by key, pairs with the same key keep their order.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// let url = Url("http://test.dev/?b=1&a=2&b=0");
+    ///
+    /// url.query_sort();
+    ///
+    /// url == "http://test.dev/?a=2&b=1&b=0"
+    /// ```
+    #[rhai_fn(global, pure)]
+    pub fn query_sort(url: &mut Url) {
+        sort_pairs(url);
+    }
+
+    /// Removes repeated query keys, keeping the first value of each key.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// let url = Url("http://test.dev/?a=1&b=2&a=3");
+    ///
+    /// url.query_dedupe();
+    ///
+    /// url == "http://test.dev/?a=1&b=2"
+    /// ```
+    #[rhai_fn(global, pure)]
+    pub fn query_dedupe(url: &mut Url) {
+        dedupe(url, false);
+    }
+
+    /// Removes repeated query keys, `keep_first` or `keep_last` selects the value to keep.
+    ///
+    /// The kept pair stays at its own position.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// let url = Url("http://test.dev/?a=1&b=2&a=3");
+    ///
+    /// url.query_dedupe("keep_last");
+    ///
+    /// url == "http://test.dev/?b=2&a=3"
+    /// ```
+    #[rhai_fn(global, name = "query_dedupe", pure, return_raw)]
+    pub fn query_dedupe_mode(url: &mut Url, mode: &str) -> Result<(), Box<EvalAltResult>> {
+        match mode {
+            "keep_first" => dedupe(url, false),
+            "keep_last" => dedupe(url, true),
+            _ => {
+                return Err(format!(
+                    "unknown dedupe mode '{mode}', expected 'keep_first' or 'keep_last'"
+                )
+                .into())
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes repeated query keys, the kept pairs are left as they are.
+    #[rhai_fn(skip)]
+    pub fn dedupe(url: &mut Url, keep_last: bool) {
+        let Some(query) = url.query() else {
+            return;
+        };
+
+        // walk backwards to keep the last occurrence, pairs are kept by their index
+        let mut seen = HashSet::new();
+        let mut kept: Vec<bool> = match keep_last {
+            true => raw_pairs(query)
+                .rev()
+                .map(|segment| seen.insert(raw_key(segment)))
+                .collect(),
+            false => raw_pairs(query)
+                .map(|segment| seen.insert(raw_key(segment)))
+                .collect(),
+        };
+        if keep_last {
+            kept.reverse();
+        }
+        drop(seen);
+
+        let mut kept = kept.into_iter();
+        retain_pairs(url, |_| kept.next().unwrap_or(true), NO_PAIRS);
+    }
+
+    /// Merges a map into the query, values are converted as in `query_set`.
+    ///
+    /// When `overwrite` is `true` the existing keys and their bracketed forms are replaced,
+    /// otherwise only the keys missing from the query, in any form, are added.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// let url = Url("http://test.dev/?page=1&q=query");
+    ///
+    /// url.query_merge(#{ page: 2, sort: "asc" }, false);
+    /// url == "http://test.dev/?page=1&q=query&sort=asc"
+    ///
+    /// url.query_merge(#{ page: 2 }, true);
+    /// url == "http://test.dev/?q=query&sort=asc&page=2"
+    /// ```
+    #[rhai_fn(global, pure)]
+    pub fn query_merge(url: &mut Url, map: Map, overwrite: bool) {
+        let existing: HashSet<_> = raw_pairs(url.query().unwrap_or("")).map(raw_key).collect();
+
+        let exists = |key: &str| existing.iter().any(|name| is_key_or_nested(name, key));
+
+        let mut pairs = Vec::new();
+        for (key, value) in map.iter() {
+            if overwrite || !exists(key) {
+                flatten_pairs(key, value, ArrayFormat::Repeat, &mut pairs);
+            }
+        }
+        drop(existing);
+
+        retain_pairs(
+            url,
+            |segment| {
+                let name = raw_key(segment);
+                !overwrite || !map.keys().any(|key| is_key_or_nested(&name, key))
+            },
+            &pairs,
+        );
+    }
+
+    /// Removes every query pair whose key is in the array.
+    ///
+    /// Not available under `no_index`.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// let url = Url("http://test.dev/?a=1&b=2&c=3");
+    ///
+    /// url.query_remove_many(["a", "c"]);
+    ///
+    /// url == "http://test.dev/?b=2"
+    /// ```
+    #[cfg(feature = "array")]
+    #[rhai_fn(global, pure)]
+    pub fn query_remove_many(url: &mut Url, keys: rhai::Array) {
+        let keys: HashSet<String> = keys.iter().map(|key| key.to_string()).collect();
+
+        retain_pairs(
+            url,
+            |segment| !keys.contains(raw_key(segment).as_ref()),
+            NO_PAIRS,
+        );
+    }
+}
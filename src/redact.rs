@@ -0,0 +1,107 @@
+use rhai::{Dynamic, EvalAltResult, Map};
+use url::{form_urlencoded, Url};
+
+use crate::query::{encoded_key, raw_key};
+
+/// Options of `redacted`, deciding which parts of a Url are masked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedactOptions {
+    /// Masks the password, enabled by default.
+    pub password: bool,
+    /// Masks the username, disabled by default.
+    pub username: bool,
+    /// Query keys whose values are masked, compared ignoring case.
+    pub query_keys: Vec<String>,
+    /// Replacement of the masked values.
+    pub mask: String,
+}
+
+impl Default for RedactOptions {
+    fn default() -> Self {
+        Self {
+            password: true,
+            username: false,
+            query_keys: vec!["token".into(), "api_key".into(), "sig".into()],
+            mask: "***".into(),
+        }
+    }
+}
+
+impl RedactOptions {
+    /// Reads the options from a Rhai map, missing keys keep their default.
+    ///
+    /// Supported keys are `password`, `username`, `query_keys` and `mask`.
+    pub fn from_map(map: &Map) -> Result<Self, Box<EvalAltResult>> {
+        let mut options = Self::default();
+
+        for (name, value) in map.iter() {
+            match name.as_str() {
+                "password" => options.password = read_bool(name, value)?,
+                "username" => options.username = read_bool(name, value)?,
+                "mask" => {
+                    options.mask = value
+                        .clone()
+                        .into_string()
+                        .map_err(|t| format!("option '{name}' must be a string, not {t}"))?
+                }
+                #[cfg(feature = "array")]
+                "query_keys" => {
+                    options.query_keys = value
+                        .clone()
+                        .into_typed_array::<rhai::ImmutableString>()
+                        .map_err(|_| format!("option '{name}' must be an array of strings"))?
+                        .into_iter()
+                        .map(Into::into)
+                        .collect()
+                }
+                _ => return Err(format!("unknown option '{name}'").into()),
+            }
+        }
+
+        Ok(options)
+    }
+
+    /// Returns a copy of the Url with the sensitive parts masked.
+    pub fn redact(&self, url: &Url) -> Url {
+        let mut url = url.clone();
+
+        // the setters only fail on Urls that cannot have credentials, which have none
+        if self.password && url.password().is_some() {
+            let _ = url.set_password(Some(&self.mask));
+        }
+        if self.username && !url.username().is_empty() {
+            let _ = url.set_username(&self.mask);
+        }
+
+        if let Some(query) = url.query().filter(|_| !self.query_keys.is_empty()) {
+            let mask: String = form_urlencoded::byte_serialize(self.mask.as_bytes()).collect();
+
+            // only rewrite the masked pairs, the others keep their original encoding
+            let query = query
+                .split('&')
+                .map(|segment| {
+                    let key = raw_key(segment);
+                    let sensitive = !segment.is_empty()
+                        && self.query_keys.iter().any(|k| k.eq_ignore_ascii_case(&key));
+
+                    if sensitive {
+                        format!("{}={mask}", encoded_key(segment))
+                    } else {
+                        segment.to_string()
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("&");
+            url.set_query(Some(&query));
+        }
+
+        url
+    }
+}
+
+/// Reads a boolean option.
+fn read_bool(name: &str, value: &Dynamic) -> Result<bool, Box<EvalAltResult>> {
+    value
+        .as_bool()
+        .map_err(|_| format!("option '{name}' must be a boolean").into())
+}
@@ -0,0 +1,196 @@
+use rhai::{Dynamic, EvalAltResult, FuncRegistration, Module, Shared, INT};
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+use url::Url;
+
+/// Schemes with a default port hardcoded by the WHATWG Url standard.
+const SPECIAL_SCHEMES: [&str; 6] = ["http", "https", "ws", "wss", "ftp", "file"];
+
+/// Registry of custom schemes and their default ports, such as `redis` → 6379.
+///
+/// The registry is shared: clones registered into several packages see the same schemes,
+/// and schemes registered by the host after the package is built are honored.
+///
+/// Schemes registered by scripts, see [`UrlPackageBuilder::script_schemes`][crate::UrlPackageBuilder::script_schemes],
+/// are kept in a registry local to the package and never reach the host registry.
+#[derive(Debug, Clone, Default)]
+pub struct SchemeRegistry {
+    /// The schemes registered in this registry.
+    schemes: Shared<RwLock<BTreeMap<String, u16>>>,
+    /// The host registry extended by the registry of a package, read first and never written.
+    host: Option<Box<SchemeRegistry>>,
+}
+
+impl PartialEq for SchemeRegistry {
+    fn eq(&self, other: &Self) -> bool {
+        (Shared::ptr_eq(&self.schemes, &other.schemes) || *self.read() == *other.read())
+            && self.host == other.host
+    }
+}
+
+impl Eq for SchemeRegistry {}
+
+impl SchemeRegistry {
+    /// Creates an empty registry.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a registry local to a package, extending this one.
+    ///
+    /// The schemes of this registry take precedence and cannot be registered again.
+    pub(crate) fn extend(&self) -> Self {
+        Self {
+            schemes: Shared::default(),
+            host: Some(Box::new(self.clone())),
+        }
+    }
+
+    /// Registers the default port of a custom scheme, replacing any previous port.
+    ///
+    /// Schemes are case-insensitive. The special schemes `http`, `https`, `ws`, `wss`, `ftp`
+    /// and `file` already have a default port and cannot be registered, nor can the schemes
+    /// of the host registry from the registry of a package.
+    pub fn register(&self, scheme: &str, port: u16) -> Result<(), Box<EvalAltResult>> {
+        let scheme = scheme.to_ascii_lowercase();
+
+        let mut chars = scheme.chars();
+        let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+            && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+        if !valid {
+            return Err(format!("invalid scheme '{scheme}'").into());
+        }
+        if SPECIAL_SCHEMES.contains(&scheme.as_str()) {
+            return Err(format!("cannot register the special scheme '{scheme}'").into());
+        }
+        if self
+            .host
+            .as_ref()
+            .is_some_and(|host| host.default_port(&scheme).is_some())
+        {
+            return Err(format!("cannot register the host scheme '{scheme}'").into());
+        }
+
+        self.schemes
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(scheme, port);
+        Ok(())
+    }
+
+    /// Returns the registered default port of a scheme.
+    pub fn default_port(&self, scheme: &str) -> Option<u16> {
+        let scheme = scheme.to_ascii_lowercase();
+        self.host
+            .as_ref()
+            .and_then(|host| host.default_port(&scheme))
+            .or_else(|| self.read().get(&scheme).copied())
+    }
+
+    /// Returns the default port of a scheme, either special or registered.
+    pub fn known_port(&self, scheme: &str) -> Option<u16> {
+        match scheme.to_ascii_lowercase().as_str() {
+            "http" | "ws" => Some(80),
+            "https" | "wss" => Some(443),
+            "ftp" => Some(21),
+            scheme => self.default_port(scheme),
+        }
+    }
+
+    /// Returns the explicit port of a Url, or the default port of its scheme,
+    /// either special or registered.
+    pub fn effective_port(&self, url: &Url) -> Option<u16> {
+        url.port_or_known_default()
+            .or_else(|| self.default_port(url.scheme()))
+    }
+
+    /// Removes the port of a Url when it is the registered default port of its scheme,
+    /// as `url::Url` does for the special schemes.
+    pub fn normalize(&self, url: &mut Url) {
+        if url.port().is_some() && url.port() == self.default_port(url.scheme()) {
+            // cannot fail, the Url already has a port
+            let _ = url.set_port(None);
+        }
+    }
+
+    /// Locks the registry for reading, ignoring poisoning.
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, BTreeMap<String, u16>> {
+        self.schemes.read().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Documentation of the `effective_port` getter.
+#[cfg(feature = "metadata")]
+const EFFECTIVE_PORT_DOCS: &str = "\
+/// Gets the Url port, or the default port of its scheme when the port is omitted.
+///
+/// Default ports are known for the special schemes such as `http`, and for the custom
+/// schemes registered by the host or with `register_scheme`. Returns `()` when unknown.
+///
+/// ### Example
+///
+/// ```js
+/// let url = Url(\"https://test.dev/\")
+/// let port = url.effective_port // 443
+/// ```";
+
+/// Documentation of the `register_scheme` function.
+#[cfg(feature = "metadata")]
+const REGISTER_SCHEME_DOCS: &str = "\
+/// Registers the default port of a custom scheme, such as `postgres` → 5432.
+///
+/// Urls with a registered scheme omit the default port, like `url::Url` does for `http`,
+/// and report it with `effective_port`. The special schemes and the schemes registered
+/// by the host cannot be registered.
+///
+/// The scheme is only known to the package running the script, and the function is only
+/// available when the host enables it with `UrlPackageBuilder::script_schemes`.
+///
+/// ### Example
+///
+/// ```js
+/// register_scheme(\"postgres\", 5432);
+///
+/// let url = Url(\"postgres://user@db.test.dev:5432/app\")
+/// let href = url.href // 'postgres://user@db.test.dev/app'
+/// ```";
+
+/// Registers the `effective_port` getter and, when scripts can register schemes,
+/// the `register_scheme` function, both using the given registry.
+pub fn register_functions(lib: &mut Module, schemes: &SchemeRegistry, scripts: bool) {
+    let getter = FuncRegistration::new_getter("effective_port");
+    #[cfg(feature = "metadata")]
+    let getter = getter
+        .with_params_info(["url: &mut Url", "Dynamic"])
+        .with_comments([EFFECTIVE_PORT_DOCS]);
+
+    let registry = schemes.clone();
+    getter.set_into_module(lib, move |url: &mut Url| {
+        registry
+            .effective_port(url)
+            .map_or(Dynamic::UNIT, |port| INT::from(port).into())
+    });
+
+    if scripts {
+        let function = FuncRegistration::new("register_scheme");
+        #[cfg(feature = "metadata")]
+        let function = function
+            .with_params_info([
+                "scheme: &str",
+                "port: INT",
+                "Result<(), Box<EvalAltResult>>",
+            ])
+            .with_comments([REGISTER_SCHEME_DOCS]);
+
+        let registry = schemes.clone();
+        function.set_into_module(
+            lib,
+            move |scheme: &str, port: INT| -> Result<(), Box<EvalAltResult>> {
+                let port = u16::try_from(port)
+                    .map_err(|_| Box::<EvalAltResult>::from(format!("invalid port {port}")))?;
+                registry.register(scheme, port)
+            },
+        );
+    }
+}
@@ -0,0 +1,266 @@
+#[allow(unused_imports)]
+use rhai::plugin::*;
+
+use url::{quirks, Url};
+
+use crate::options::{SetterMode, UrlOptions};
+
+/// A `url::quirks` setter, `Err(())` when the value is refused.
+pub type Setter = fn(&mut Url, &str) -> Result<(), ()>;
+
+/// The browser setters, by property name.
+pub const SETTERS: [(&str, Setter); 9] = [
+    ("protocol", quirks::set_protocol),
+    ("username", quirks::set_username),
+    ("password", quirks::set_password),
+    ("host", quirks::set_host),
+    ("hostname", quirks::set_hostname),
+    ("port", quirks::set_port),
+    ("pathname", |url, value| {
+        quirks::set_pathname(url, value);
+        Ok(())
+    }),
+    ("search", |url, value| {
+        quirks::set_search(url, value);
+        Ok(())
+    }),
+    ("hash", |url, value| {
+        quirks::set_hash(url, value);
+        Ok(())
+    }),
+];
+
+/// Applies a browser setter, honoring the scheme policy, the setter mode and the resource limits.
+///
+/// A refused value is ignored, as browsers do, unless the setter mode is strict.
+pub fn set(
+    options: &UrlOptions,
+    url: &mut Url,
+    property: &str,
+    value: &str,
+) -> Result<(), Box<EvalAltResult>> {
+    let setter = SETTERS
+        .iter()
+        .find(|(name, _)| *name == property)
+        .map(|(_, setter)| setter)
+        .expect("browser setter");
+
+    let mut changed = url.clone();
+    if setter(&mut changed, value).is_err() {
+        return match options.setter_mode {
+            SetterMode::Strict => Err(format!("cannot set {property} '{value}'").into()),
+            SetterMode::Lenient => Ok(()),
+        };
+    }
+
+    if changed.scheme() != url.scheme() {
+        options.check_scheme(changed.scheme())?;
+    }
+    options.schemes.normalize(&mut changed);
+    options.check_limits(&changed)?;
+
+    *url = changed;
+    Ok(())
+}
+
+#[export_module]
+pub mod web_module {
+    use super::set;
+    use crate::options::UrlOptions;
+    use url::{quirks, Url};
+
+    /// Sets the full Url, as `URL.href` does, an invalid Url raises an error.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// // with web_compat
+    /// let url = Url("http://test.dev/");
+    /// url.href = "https://example.com/a?b=1";
+    ///
+    /// url.pathname; // '/a'
+    /// ```
+    #[rhai_fn(global, set = "href", pure, return_raw)]
+    pub fn set_href(url: &mut Url, value: &str) -> Result<(), Box<EvalAltResult>> {
+        *url = UrlOptions::default().parse(value)?;
+        Ok(())
+    }
+
+    /// Gets the scheme with its delimiter, such as `https:`, as `URL.protocol` does.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// // with web_compat
+    /// let url = Url("https://test.dev/");
+    ///
+    /// url.protocol; // 'https:'
+    /// url.scheme; // 'https'
+    /// ```
+    #[rhai_fn(global, get = "protocol", pure)]
+    pub fn protocol(url: &mut Url) -> ImmutableString {
+        quirks::protocol(url).into()
+    }
+
+    /// Sets the scheme, with or without the `:` delimiter, as `URL.protocol` does.
+    #[rhai_fn(global, set = "protocol", pure, return_raw)]
+    pub fn set_protocol(url: &mut Url, value: &str) -> Result<(), Box<EvalAltResult>> {
+        set(&UrlOptions::default(), url, "protocol", value)
+    }
+
+    /// Gets the username, as `URL.username` does.
+    #[rhai_fn(global, get = "username", pure)]
+    pub fn username(url: &mut Url) -> ImmutableString {
+        quirks::username(url).into()
+    }
+
+    /// Sets the username, as `URL.username` does.
+    #[rhai_fn(global, set = "username", pure, return_raw)]
+    pub fn set_username(url: &mut Url, value: &str) -> Result<(), Box<EvalAltResult>> {
+        set(&UrlOptions::default(), url, "username", value)
+    }
+
+    /// Gets the password, as `URL.password` does.
+    #[rhai_fn(global, get = "password", pure)]
+    pub fn password(url: &mut Url) -> ImmutableString {
+        quirks::password(url).into()
+    }
+
+    /// Sets the password, as `URL.password` does.
+    #[rhai_fn(global, set = "password", pure, return_raw)]
+    pub fn set_password(url: &mut Url, value: &str) -> Result<(), Box<EvalAltResult>> {
+        set(&UrlOptions::default(), url, "password", value)
+    }
+
+    /// Gets the host with its port, such as `test.dev:8080`, as `URL.host` does.
+    #[rhai_fn(global, get = "host", pure)]
+    pub fn host(url: &mut Url) -> ImmutableString {
+        quirks::host(url).into()
+    }
+
+    /// Sets the host, with or without a port, as `URL.host` does.
+    #[rhai_fn(global, set = "host", pure, return_raw)]
+    pub fn set_host(url: &mut Url, value: &str) -> Result<(), Box<EvalAltResult>> {
+        set(&UrlOptions::default(), url, "host", value)
+    }
+
+    /// Gets the host without its port, as `URL.hostname` does.
+    #[rhai_fn(global, get = "hostname", pure)]
+    pub fn hostname(url: &mut Url) -> ImmutableString {
+        quirks::hostname(url).into()
+    }
+
+    /// Sets the host without changing the port, as `URL.hostname` does.
+    #[rhai_fn(global, set = "hostname", pure, return_raw)]
+    pub fn set_hostname(url: &mut Url, value: &str) -> Result<(), Box<EvalAltResult>> {
+        set(&UrlOptions::default(), url, "hostname", value)
+    }
+
+    /// Gets the port as a string, empty for the default port, as `URL.port` does.
+    #[rhai_fn(global, get = "port", pure)]
+    pub fn port(url: &mut Url) -> ImmutableString {
+        quirks::port(url).into()
+    }
+
+    /// Sets the port from a string, an empty string removes it, as `URL.port` does.
+    #[rhai_fn(global, set = "port", pure, return_raw)]
+    pub fn set_port(url: &mut Url, value: &str) -> Result<(), Box<EvalAltResult>> {
+        set(&UrlOptions::default(), url, "port", value)
+    }
+
+    /// Gets the path, as `URL.pathname` does.
+    #[rhai_fn(global, get = "pathname", pure)]
+    pub fn pathname(url: &mut Url) -> ImmutableString {
+        quirks::pathname(url).into()
+    }
+
+    /// Sets the path, as `URL.pathname` does, Urls that cannot be a base are left unchanged.
+    #[rhai_fn(global, set = "pathname", pure, return_raw)]
+    pub fn set_pathname(url: &mut Url, value: &str) -> Result<(), Box<EvalAltResult>> {
+        set(&UrlOptions::default(), url, "pathname", value)
+    }
+
+    /// Gets the query string with its `?` delimiter, empty without a query string,
+    /// as `URL.search` does.
+    ///
+    /// Unlike `query`, an empty query string `?` is also read as `""`.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// // with web_compat
+    /// let url = Url("http://test.dev/?q=1#top");
+    ///
+    /// url.search; // '?q=1'
+    /// url.query; // 'q=1'
+    /// url.hash; // '#top'
+    /// Url("http://test.dev/?").search; // ''
+    /// ```
+    #[rhai_fn(global, get = "search", pure)]
+    pub fn search(url: &mut Url) -> ImmutableString {
+        quirks::search(url).into()
+    }
+
+    /// Sets the query string, with or without the `?` delimiter, an empty string removes it,
+    /// as `URL.search` does.
+    ///
+    /// Setting `query` to `""` keeps an empty query string instead, `()` removes it.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// // with web_compat
+    /// let url = Url("http://test.dev/?a=1#top");
+    ///
+    /// url.search = "?q=2";
+    /// url.href; // 'http://test.dev/?q=2#top'
+    ///
+    /// url.search = "";
+    /// url.href; // 'http://test.dev/#top'
+    /// url.has_query; // false
+    /// ```
+    #[rhai_fn(global, set = "search", pure, return_raw)]
+    pub fn set_search(url: &mut Url, value: &str) -> Result<(), Box<EvalAltResult>> {
+        set(&UrlOptions::default(), url, "search", value)
+    }
+
+    /// Gets the fragment with its `#` delimiter, empty without a fragment, as `URL.hash` does.
+    ///
+    /// Replaces the `hash` alias of `fragment`.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// // with web_compat
+    /// let url = Url("http://test.dev/#top");
+    ///
+    /// url.hash; // '#top'
+    /// url.fragment; // 'top'
+    /// ```
+    #[rhai_fn(global, get = "hash", pure)]
+    pub fn hash(url: &mut Url) -> ImmutableString {
+        quirks::hash(url).into()
+    }
+
+    /// Sets the fragment, with or without the `#` delimiter, an empty string removes it,
+    /// as `URL.hash` does.
+    ///
+    /// Replaces the `hash` alias of `fragment`.
+    ///
+    /// ### Example
+    ///
+    /// ```js
+    /// // with web_compat
+    /// let url = Url("http://test.dev/#a");
+    ///
+    /// url.hash = "#top";
+    /// url.href; // 'http://test.dev/#top'
+    ///
+    /// url.hash = "";
+    /// url.href; // 'http://test.dev/'
+    /// ```
+    #[rhai_fn(global, set = "hash", pure, return_raw)]
+    pub fn set_hash(url: &mut Url, value: &str) -> Result<(), Box<EvalAltResult>> {
+        set(&UrlOptions::default(), url, "hash", value)
+    }
+}
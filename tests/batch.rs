@@ -0,0 +1,217 @@
+#![cfg(feature = "array")]
+
+use rhai::{packages::Package, Engine, EvalAltResult};
+
+use rhai_url::{FunctionGroup, UrlPackage};
+
+/**
+ * Tests parse_urls()
+ */
+#[test]
+fn test_parse_urls() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    let package = UrlPackage::new();
+    package.register_into_engine(&mut engine);
+
+    assert_eq!(
+        engine.eval::<String>(
+            r#"
+        let result = parse_urls(["http://test.dev/", "not a url", Url("https://test.dev/"), 42]);
+        let urls = result.urls.map(|url| url.href);
+        let errors = result.errors.map(|err| `${err.index}:${err.input}:${err.kind}`);
+        `${urls} ${errors}`
+        "#
+        )?,
+        r#"["http://test.dev/", "https://test.dev/"] ["1:not a url:invalid_url", "3:42:invalid_url"]"#,
+        "it should collect the urls and the errors"
+    );
+
+    assert!(
+        engine
+            .eval::<String>(r#"parse_urls(["not a url"]).errors[0].message"#)?
+            .contains("relative URL without a base"),
+        "it should keep the error message"
+    );
+
+    Ok(())
+}
+
+/**
+ * Tests urls_join()
+ */
+#[test]
+fn test_urls_join() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    let package = UrlPackage::new();
+    package.register_into_engine(&mut engine);
+
+    assert_eq!(
+        engine.eval::<String>(
+            r##"
+        let urls = urls_join(Url("http://test.dev/a/b"), ["c", "../d", "?q=1", "#top", "//example.com/", "http://[::1", 42]);
+        `${urls.map(|url| if url == () { "()" } else { url.href })}`
+        "##
+        )?,
+        r##"["http://test.dev/a/c", "http://test.dev/d", "http://test.dev/a/b?q=1", "http://test.dev/a/b#top", "http://example.com/", "()", "()"]"##,
+        "it should resolve the entries and keep the positions of the invalid ones"
+    );
+
+    assert!(
+        engine
+            .eval::<rhai::Array>(r#"urls_join("not a url", ["a"])"#)
+            .is_err(),
+        "it should be an error on an invalid base"
+    );
+
+    Ok(())
+}
+
+/**
+ * Tests urls_normalize()
+ */
+#[test]
+fn test_urls_normalize() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    let package = UrlPackage::new();
+    package.register_into_engine(&mut engine);
+
+    assert_eq!(
+        engine.eval::<String>(
+            r#"
+        let urls = urls_normalize(["HTTP://Test.DEV:80/a/?b=2&a=1#top", "http://test.dev/?", "nope"]);
+        `${urls.map(|url| if url == () { "()" } else { url.href })}`
+        "#
+        )?,
+        r#"["http://test.dev/a/?a=1&b=2", "http://test.dev/", "()"]"#,
+        "it should normalize with the default options"
+    );
+
+    assert_eq!(
+        engine.eval::<String>(
+            r#"
+        let options = #{ remove_fragment: false, sort_query: false, remove_trailing_slash: true, remove_query_keys: ["utm_source"] };
+        let urls = urls_normalize(["http://test.dev/a/?b=2&utm_source=x&a=1#top", "http://test.dev/"], options);
+        `${urls.map(|url| url.href)}`
+        "#
+        )?,
+        r##"["http://test.dev/a?b=2&a=1#top", "http://test.dev/"]"##,
+        "it should normalize with the given options"
+    );
+
+    assert!(
+        engine
+            .eval::<rhai::Array>(r#"urls_normalize([], #{ unknown: true })"#)
+            .is_err(),
+        "it should be an error on an unknown option"
+    );
+
+    Ok(())
+}
+
+/**
+ * Tests urls_dedupe(), urls_group_by_host() and urls_filter_by_pattern()
+ */
+#[test]
+fn test_urls_reductions() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    let package = UrlPackage::new();
+    package.register_into_engine(&mut engine);
+
+    assert_eq!(
+        engine.eval::<String>(
+            r#"
+        let urls = urls_dedupe(["http://test.dev/", "HTTP://test.dev:80", "nope", Url("http://test.dev/a"), "http://test.dev/a"]);
+        `${urls.map(|url| url.href)}`
+        "#
+        )?,
+        r#"["http://test.dev/", "http://test.dev/a"]"#,
+        "it should remove the repeated urls and the invalid entries"
+    );
+
+    assert_eq!(
+        engine.eval::<String>(
+            r#"
+        let groups = urls_group_by_host(["http://a.dev/1", "mailto:me@a.dev", "http://b.dev/", "https://a.dev/2", "nope"]);
+        `${groups.keys()} ${groups["a.dev"].map(|url| url.href)} ${groups[""].len()}`
+        "#
+        )?,
+        r#"["", "a.dev", "b.dev"] ["http://a.dev/1", "https://a.dev/2"] 1"#,
+        "it should group the urls by host"
+    );
+
+    let cases = [
+        ("https://*.test.dev/*", r#"["https://a.test.dev/x"]"#),
+        (
+            "*test.dev*",
+            r#"["https://a.test.dev/x", "https://test.dev/y", "http://test.dev/z?q=1"]"#,
+        ),
+        ("http://test.dev/z?q=1", r#"["http://test.dev/z?q=1"]"#),
+        (
+            "http*://test.dev/*",
+            r#"["https://test.dev/y", "http://test.dev/z?q=1"]"#,
+        ),
+        ("*/y", r#"["https://test.dev/y"]"#),
+        ("https://test.dev", "[]"),
+    ];
+    for (pattern, expected) in cases {
+        let script = format!(
+            r#"
+        let urls = urls_filter_by_pattern(["https://a.test.dev/x", "https://test.dev/y", "http://test.dev/z?q=1", "nope"], "{pattern}");
+        `${{urls.map(|url| url.href)}}`
+        "#
+        );
+        assert_eq!(
+            engine.eval::<String>(&script)?,
+            expected,
+            "it should filter the urls matching {pattern}"
+        );
+    }
+
+    Ok(())
+}
+
+/**
+ * Tests the batch functions honor the options and the function groups
+ */
+#[test]
+fn test_batch_options() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    let package = UrlPackage::builder().allowed_schemes(["https"]).build();
+    package.register_into_engine(&mut engine);
+
+    assert_eq!(
+        engine.eval::<String>(
+            r#"
+        let result = parse_urls(["https://test.dev/", "http://test.dev/"]);
+        `${result.urls.len()} ${result.errors[0].kind}`
+        "#
+        )?,
+        "1 scheme_not_allowed",
+        "it should report the refused schemes"
+    );
+
+    assert_eq!(
+        engine
+            .eval::<String>(r#"`${urls_join("https://test.dev/", ["a", "http://test.dev/"])}`"#)?,
+        r#"[https://test.dev/a, ()]"#,
+        "it should refuse the joined urls with a scheme that is not allowed"
+    );
+
+    let mut engine = Engine::new();
+    UrlPackage::builder()
+        .groups([FunctionGroup::Core])
+        .build()
+        .register_into_engine(&mut engine);
+
+    assert!(
+        engine.eval::<rhai::Map>(r#"parse_urls([])"#).is_err(),
+        "it should not register the batch functions without the group"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,56 @@
+#![cfg(feature = "metadata")]
+
+/**
+ * Tests the generated definitions
+ */
+#[test]
+fn test_definitions() {
+    let definitions = rhai_url::definitions();
+
+    assert!(
+        definitions.starts_with("module static;"),
+        "it should generate a definition file"
+    );
+
+    assert!(
+        definitions.contains("fn Url(url: string) -> Url;"),
+        "it should define the functions with the Url type name"
+    );
+
+    assert!(
+        definitions.contains("fn query_get(url: Url, key: string) -> string;")
+            && definitions.contains("fn get effective_port(url: Url) -> ?;")
+            && definitions.contains("op ==(Origin, string) -> bool;"),
+        "it should map the Rust types to script types"
+    );
+
+    assert!(
+        !["Result<", "Box<", "&str", "ImmutableString", "Dynamic"]
+            .iter()
+            .any(|rust| definitions.contains(rust)),
+        "it should not leak Rust types"
+    );
+}
+
+/**
+ * Tests the generated function index
+ */
+#[test]
+fn test_function_index() {
+    let index = rhai_url::function_index();
+
+    assert!(
+        index.trim_start().starts_with('['),
+        "it should generate a JSON array"
+    );
+
+    assert!(
+        index.contains(r#""signature": "query_get(url: Url, key: &str) -> String""#),
+        "it should index the function signatures"
+    );
+
+    assert!(
+        index.contains(r#""examples": ["#),
+        "it should index the examples"
+    );
+}
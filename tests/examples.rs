@@ -0,0 +1,277 @@
+// the examples use arrays
+#![cfg(all(feature = "metadata", feature = "array"))]
+
+use rhai::packages::Package;
+use rhai::Engine;
+
+use rhai_url::UrlPackage;
+
+/// A ```js example of a function doc comment.
+struct Example {
+    /// The function the example documents.
+    function: String,
+    /// The example script.
+    code: String,
+}
+
+/// Reads the examples from the function index generated by `build.rs`.
+fn examples() -> Vec<Example> {
+    let index: serde_json::Value = serde_json::from_str(rhai_url::function_index()).unwrap();
+
+    let mut examples = Vec::new();
+    for function in index.as_array().unwrap() {
+        for code in function["examples"].as_array().unwrap() {
+            examples.push(Example {
+                function: function["signature"].as_str().unwrap().to_string(),
+                code: code.as_str().unwrap().to_string(),
+            });
+        }
+    }
+
+    examples
+}
+
+/// Returns the end of the code of a line, before any `//` comment outside of strings.
+fn code_end(line: &str) -> usize {
+    let mut quote = None;
+    let mut chars = line.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        match (quote, c) {
+            (Some(_), '\\') => {
+                chars.next();
+            }
+            (Some(q), c) if c == q => quote = None,
+            (None, '"' | '\'' | '`') => quote = Some(c),
+            (None, '/') if chars.peek().is_some_and(|(_, c)| *c == '/') => return i,
+            _ => {}
+        }
+    }
+
+    line.len()
+}
+
+/// Returns the bracket depth change of some code, ignoring strings.
+fn depth_change(code: &str) -> isize {
+    let mut quote = None;
+    let mut depth = 0;
+    let mut chars = code.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(_), '\\') => {
+                chars.next();
+            }
+            (Some(q), c) if c == q => quote = None,
+            (None, '"' | '\'' | '`') => quote = Some(c),
+            (None, '(' | '[' | '{') => depth += 1,
+            (None, ')' | ']' | '}') => depth -= 1,
+            _ => {}
+        }
+    }
+
+    depth
+}
+
+/// Reads the value asserted by a comment, such as `// 'https'`, `// "x"`, `// 443`
+/// or `// ["a", "b"]`, arrays being compared with their string value.
+fn expected_value(comment: &str) -> Option<String> {
+    let value = comment.trim();
+
+    if value.starts_with('[') && value.ends_with(']') && depth_change(value) == 0 {
+        return Some(value.to_string());
+    }
+
+    for quote in ['\'', '"'] {
+        if let Some(inner) = value
+            .strip_prefix(quote)
+            .and_then(|v| v.strip_suffix(quote))
+        {
+            if !inner.contains(quote) {
+                return Some(inner.to_string());
+            }
+        }
+    }
+
+    let is_literal = value == "true"
+        || value == "false"
+        || value == "()"
+        || (!value.is_empty() && value.parse::<f64>().is_ok());
+    is_literal.then(|| value.replace("()", ""))
+}
+
+/// Splits a bare comparison such as `url == "http://test.dev/"` at its top-level `==`.
+fn split_comparison(statement: &str) -> Option<(&str, &str)> {
+    let keywords = [
+        "let ", "const ", "for ", "if ", "while ", "loop ", "print(", "fn ",
+    ];
+    if keywords.iter().any(|k| statement.starts_with(k)) {
+        return None;
+    }
+
+    let mut quote = None;
+    let mut depth = 0;
+    let mut chars = statement.char_indices();
+
+    while let Some((i, c)) = chars.next() {
+        match (quote, c) {
+            (Some(_), '\\') => {
+                chars.next();
+            }
+            (Some(q), c) if c == q => quote = None,
+            (None, '"' | '\'' | '`') => quote = Some(c),
+            (None, '(' | '[' | '{') => depth += 1,
+            (None, ')' | ']' | '}') => depth -= 1,
+            (None, '|') => return None,
+            (None, '=') if depth == 0 && statement[i..].starts_with("== ") => {
+                return Some((statement[..i].trim(), statement[i + 2..].trim()));
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Rewrites an example into a script raising an error when an assertion fails.
+///
+/// Statements are separated, a missing `;` is added, and the assertions are:
+///
+/// * a comment holding a literal, `let port = url.port // 443` or `url.query; // 'a=1'`,
+///   checked against the string value of the variable or the expression, an array
+///   literal such as `// ["a", "b"]` against the string value of the array
+/// * a bare comparison, `url == "http://test.dev/"`, checking that both sides
+///   have the same string value, as the docs compare Urls to strings
+///
+/// A comment after code must hold a literal and end its statement, and a result cannot
+/// be written on a line of its own, so that no expectation goes unchecked.
+fn instrument(code: &str) -> Result<String, String> {
+    let mut script = String::new();
+    let mut statement = String::new();
+    let mut depth = 0;
+
+    for line in code.lines() {
+        let end = code_end(line);
+        let (code, comment) = line.split_at(end);
+        depth += depth_change(code);
+
+        let expected = comment.strip_prefix("//").and_then(expected_value);
+        match (
+            code.trim().is_empty(),
+            comment.trim_start_matches('/').trim(),
+        ) {
+            (_, "") => {}
+            (true, _) if expected.is_some() => {
+                return Err(format!("a result on its own line is not checked: {line}"));
+            }
+            (true, _) => {}
+            (false, _) if expected.is_none() => {
+                return Err(format!("a comment after code must be a literal: {line}"));
+            }
+            (false, _) if depth > 0 => {
+                return Err(format!("a result inside a block is not checked: {line}"));
+            }
+            (false, _) => {}
+        }
+
+        statement.push_str(code.trim_end());
+        statement.push('\n');
+        if depth > 0 {
+            continue;
+        }
+
+        let body = std::mem::take(&mut statement);
+        let body = body.trim().trim_end_matches(';').trim();
+        if body.is_empty() {
+            continue;
+        }
+
+        // `expected` is a script expression evaluating to a string
+        let check = |value: &str, expected: &str| {
+            format!(
+                "{{ let actual = `${{{value}}}`; let expected = {expected}; if actual != expected {{ throw `{}: expected '${{expected}}', got '${{actual}}'`; }} }}\n",
+                body.replace('`', "'").replace('$', "\\$").replace('\n', " ")
+            )
+        };
+
+        match (expected, body.strip_prefix("let ")) {
+            (Some(expected), Some(rest)) => {
+                let name = rest.split(['=', ' ']).next().unwrap();
+                script += &format!("{body};\n");
+                script += &check(name, &format!("{expected:?}"));
+            }
+            (Some(expected), None) => {
+                let value = body
+                    .strip_prefix("print(")
+                    .and_then(|v| v.strip_suffix(')'))
+                    .unwrap_or(body);
+                script += &check(value, &format!("{expected:?}"));
+            }
+            (None, _) => match split_comparison(body) {
+                Some((value, expected)) => script += &check(value, &format!("`${{{expected}}}`")),
+                None => script += &format!("{body};\n"),
+            },
+        }
+    }
+
+    Ok(script)
+}
+
+/**
+ * Tests the ```js examples of the doc comments
+ */
+#[test]
+fn test_doc_examples() {
+    let examples = examples();
+    assert!(!examples.is_empty(), "it should find the doc examples");
+
+    let mut failures = Vec::new();
+    for example in &examples {
+        let mut engine = Engine::new();
+        engine.on_print(|_| {});
+
+        // the browser properties replace some Url properties, such as `hash`
+        let package = UrlPackage::builder()
+            .file_paths(true)
+            .script_schemes(true)
+            .web_compat(example.code.starts_with("// with web_compat"))
+            .build();
+        package.register_into_engine(&mut engine);
+
+        let result = instrument(&example.code)
+            .and_then(|script| engine.run(&script).map_err(|err| err.to_string()));
+        if let Err(err) = result {
+            failures.push(format!("{}\n{}\n=> {err}", example.function, example.code));
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "it should run every doc example, {} of {} failed:\n\n{}",
+        failures.len(),
+        examples.len(),
+        failures.join("\n\n")
+    );
+}
+
+/**
+ * Tests the examples cannot hold expectations that are not checked
+ */
+#[test]
+fn test_unchecked_comments() {
+    for code in [
+        "let x = 1; // one",
+        "let x = 1;\n// 1",
+        "for i in [1] {\n    let x = i; // 1\n}",
+    ] {
+        assert!(
+            instrument(code).is_err(),
+            "it should refuse the unchecked comment of {code:?}"
+        );
+    }
+
+    assert!(
+        instrument("// a note\nlet x = 1; // 1").is_ok(),
+        "it should accept notes on their own line and literal results"
+    );
+}
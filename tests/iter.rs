@@ -0,0 +1,128 @@
+use rhai::{packages::Package, Engine, EvalAltResult};
+
+use rhai_url::UrlPackage;
+
+/**
+ * Tests for loops over .query_pairs()
+ */
+#[test]
+fn test_query_pairs_iterator() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    let package = UrlPackage::new();
+    package.register_into_engine(&mut engine);
+
+    assert_eq!(
+        engine.eval::<String>(
+            r#"
+        let url = Url("http://test.dev/?q=a+b&&empty=&b=%26");
+        let out = "";
+        for pair in url.query_pairs() {
+            out += "[" + pair.key + ":" + pair.value + "]";
+        }
+        out
+        "#
+        )?,
+        "[q:a b][empty:][b:&]",
+        "it should iterate the decoded pairs, skipping empty segments"
+    );
+
+    assert_eq!(
+        engine.eval::<i64>(
+            r#"
+        let count = 0;
+        for pair in Url("http://test.dev/").query_pairs() {
+            count += 1;
+        }
+        count
+        "#
+        )?,
+        0,
+        "it should not iterate when there is no query string"
+    );
+
+    assert_eq!(
+        engine.eval::<String>(
+            r#"
+        let out = "";
+        for (pair, i) in Url("http://test.dev/?a=1&b=2").query_pairs() {
+            out += `${i}:${pair}|`;
+        }
+        out
+        "#
+        )?,
+        "0:a=1|1:b=2|",
+        "it should provide the counter as the second loop variable"
+    );
+
+    assert_eq!(
+        engine.eval::<String>(
+            r#"
+        let out = "";
+        for (k, v) in Url("http://test.dev/?a=1&b=2").query_pairs() {
+            out += `${type_of(k)} ${k} ${type_of(v)} ${v}|`;
+        }
+        out
+        "#
+        )?,
+        "QueryPair a=1 i64 0|QueryPair b=2 i64 1|",
+        "it should bind the pair and the counter in the (k, v) form, not the key and value"
+    );
+
+    Ok(())
+}
+
+/**
+ * Tests for loops over .path_segments()
+ */
+#[test]
+fn test_path_segments_iterator() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    let package = UrlPackage::new();
+    package.register_into_engine(&mut engine);
+
+    assert_eq!(
+        engine.eval::<String>(
+            r#"
+        let out = "";
+        for segment in Url("http://test.dev/a/b%20c/").path_segments() {
+            out += "[" + segment + "]";
+        }
+        out
+        "#
+        )?,
+        "[a][b%20c][]",
+        "it should iterate the encoded segments, including the trailing empty one"
+    );
+
+    assert_eq!(
+        engine.eval::<String>(
+            r#"
+        let out = "";
+        for segment in Url("http://test.dev").path_segments() {
+            out += "[" + segment + "]";
+        }
+        out
+        "#
+        )?,
+        "[]",
+        "it should yield a single empty segment for the root path"
+    );
+
+    assert_eq!(
+        engine.eval::<i64>(
+            r#"
+        let count = 0;
+        for segment in Url("mailto:dev@test.dev").path_segments() {
+            count += 1;
+        }
+        count
+        "#
+        )?,
+        0,
+        "it should not iterate a cannot-be-a-base url"
+    );
+
+    Ok(())
+}
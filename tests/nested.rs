@@ -0,0 +1,134 @@
+#![cfg(feature = "array")]
+
+use rhai::{packages::Package, Engine, EvalAltResult};
+
+use rhai_url::UrlPackage;
+
+/**
+ * Tests query_parse_nested()
+ */
+#[test]
+fn test_query_parse_nested() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    let package = UrlPackage::new();
+    package.register_into_engine(&mut engine);
+
+    assert_eq!(
+        engine.eval::<String>(r#"query_parse_nested("a[b][c]=1&list[]=x&list[]=y").to_json()"#)?,
+        r#"{"a":{"b":{"c":"1"}},"list":["x","y"]}"#,
+        "it should parse nested maps and arrays"
+    );
+
+    assert_eq!(
+        engine.eval::<String>(r#"query_parse_nested("?a=1&a=2&b[0]=x&b[1]=y&c=a+b").to_json()"#)?,
+        r#"{"a":["1","2"],"b":["x","y"],"c":"a b"}"#,
+        "it should collect repeated keys and indices into arrays"
+    );
+
+    assert_eq!(
+        engine.eval::<String>(
+            r#"query_parse_nested("a[b][c][d]=1&x=1&y=2", #{ depth: 1, parameter_limit: 2 }).to_json()"#
+        )?,
+        r#"{"a":{"b":{"[c][d]":"1"}},"x":"1"}"#,
+        "it should honor the depth and parameter limits"
+    );
+
+    let cases = [
+        ("a[1]=b&a[0]=a", r#"{"a":["a","b"]}"#),
+        ("a[2]=c&a[]=d&a[0]=a", r#"{"a":["a","c","d"]}"#),
+        ("a[1][x]=1&a[0][x]=0", r#"{"a":[{"x":"0"},{"x":"1"}]}"#),
+        ("a[5]=b&a[2]=a", r#"{"a":["a","b"]}"#),
+        (
+            "a[999999999999]=1&a[0]=2",
+            r#"{"a":{"0":"2","999999999999":"1"}}"#,
+        ),
+        ("a[0]=x&a[21]=y", r#"{"a":{"0":"x","21":"y"}}"#),
+        ("a[1]=x&a[b]=y", r#"{"a":{"1":"x","b":"y"}}"#),
+        ("a[x]=1&a[2]=p&a[]=v", r#"{"a":{"2":"p","3":"v","x":"1"}}"#),
+        ("a[x]=1&a=v&a=w", r#"{"a":{"0":"v","1":"w","x":"1"}}"#),
+    ];
+    for (query, expected) in cases {
+        assert_eq!(
+            engine.eval::<String>(&format!(r#"query_parse_nested("{query}").to_json()"#))?,
+            expected,
+            "it should keep the indices of {query} in order"
+        );
+    }
+
+    assert_eq!(
+        engine.eval::<String>(
+            r#"query_parse_nested("a[3]=x&a[30]=y", #{ array_limit: 30 }).to_json()"#
+        )?,
+        r#"{"a":["x","y"]}"#,
+        "it should honor the array limit"
+    );
+
+    assert_eq!(
+        engine.eval::<String>(r#"query_parse_nested("c=a+b%20c", #{ encoding: "rfc3986" }).c"#)?,
+        "a+b c",
+        "it should keep '+' as a literal plus under rfc3986"
+    );
+
+    assert!(
+        engine
+            .eval::<rhai::Map>(r#"query_parse_nested("a=1", #{ unknown: 1 })"#)
+            .is_err(),
+        "it should be an error on an unknown option"
+    );
+
+    Ok(())
+}
+
+/**
+ * Tests query_stringify_nested() and the query_nested property
+ */
+#[test]
+fn test_query_stringify_nested() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    let package = UrlPackage::new();
+    package.register_into_engine(&mut engine);
+
+    assert_eq!(
+        engine.eval::<String>(r#"query_stringify_nested(#{ a: #{ b: 1 }, list: ["x", "y"] })"#)?,
+        "a%5Bb%5D=1&list%5B%5D=x&list%5B%5D=y",
+        "it should write brackets by default"
+    );
+
+    assert_eq!(
+        engine.eval::<String>(
+            r#"query_stringify_nested(#{ list: ["x y", "z"] }, #{ array_format: "indices", encoding: "rfc3986" })"#
+        )?,
+        "list%5B0%5D=x%20y&list%5B1%5D=z",
+        "it should honor the array format and encoding"
+    );
+
+    assert_eq!(
+        engine.eval::<String>(
+            r#"
+        let url = Url("http://test.dev/?filter[status]=open");
+        let query = url.query_nested;
+        query.filter.tags = ["a"];
+        url.query_nested = query;
+        url.query_nested.filter.tags[0] + " " + url.query
+        "#
+        )?,
+        "a filter%5Bstatus%5D=open&filter%5Btags%5D%5B%5D=a",
+        "it should round-trip the query through the property"
+    );
+
+    assert_eq!(
+        engine.eval::<String>(
+            r#"
+        let url = Url("http://test.dev/?a=1");
+        url.query_nested = #{};
+        url.href
+        "#
+        )?,
+        "http://test.dev/",
+        "it should remove the query on an empty map"
+    );
+
+    Ok(())
+}
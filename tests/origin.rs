@@ -0,0 +1,112 @@
+use rhai::{packages::Package, Engine, EvalAltResult};
+
+use rhai_url::UrlPackage;
+
+/**
+ * Tests origin and is_tuple_origin
+ */
+#[test]
+fn test_origin() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    let package = UrlPackage::new();
+    package.register_into_engine(&mut engine);
+
+    assert_eq!(
+        engine.eval::<String>(
+            r#"
+        let url = Url("https://user@test.dev:8443/path?q=1");
+        `${url.origin} ${type_of(url.origin)} ${url.is_tuple_origin}`
+        "#
+        )?,
+        "https://test.dev:8443 Origin true",
+        "it should serialize a tuple origin"
+    );
+
+    assert_eq!(
+        engine.eval::<String>(
+            r#"
+        let url = Url("data:text/plain,hello");
+        `${url.origin} ${url.is_tuple_origin} ${url.origin == url.origin} ${url.origin == "null"}`
+        "#
+        )?,
+        "null false false false",
+        "it should serialize an opaque origin as null and never match it"
+    );
+
+    #[cfg(feature = "array")]
+    assert!(
+        engine.eval::<bool>(
+            r#"
+        let allowed = [Origin("https://test.dev"), Origin("https://app.test.dev")];
+        Url("https://app.test.dev/login").origin in allowed
+        "#
+        )?,
+        "it should find an origin in an allowlist"
+    );
+
+    assert!(
+        engine.eval::<bool>(r#"Url("https://test.dev/").origin != "https://test.dev:8443""#)?,
+        "it should compare an origin with a string"
+    );
+
+    Ok(())
+}
+
+/**
+ * Tests is_same_origin and is_same_site
+ */
+#[test]
+fn test_same_origin_site() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    let package = UrlPackage::new();
+    package.register_into_engine(&mut engine);
+
+    assert_eq!(
+        engine.eval::<String>(
+            r#"
+        let url = Url("https://test.dev/a");
+        `${url.is_same_origin(Url("https://test.dev:443/b"))} ` +
+        `${url.is_same_origin("http://test.dev/a")} ` +
+        `${url.is_same_origin("https://app.test.dev/a")} ` +
+        `${"https://test.dev/c".is_same_origin(url)}`
+        "#
+        )?,
+        "true false false true",
+        "it should compare the scheme, host and port"
+    );
+
+    assert_eq!(
+        engine.eval::<String>(
+            r#"
+        let url = Url("https://app.test.dev/");
+        `${url.is_same_site("https://api.test.dev/")} ` +
+        `${url.is_same_site("http://app.test.dev/")} ` +
+        `${Url("https://a.example.co.uk/").is_same_site("https://b.example.co.uk/")} ` +
+        `${Url("https://a.example.co.uk/").is_same_site("https://other.co.uk/")} ` +
+        `${Url("http://127.0.0.1/").is_same_site("http://127.0.0.2/")}`
+        "#
+        )?,
+        "true false true false false",
+        "it should compare the scheme and registrable domain"
+    );
+
+    assert_eq!(
+        engine.eval::<String>(
+            r#"
+        `${Url("https://a.github.io/").is_same_site("https://b.github.io/")} ` +
+        `${Url("https://a.b.github.io/").is_same_site("https://c.b.github.io/")} ` +
+        `${Url("https://a.tokyo.jp/").is_same_site("https://b.tokyo.jp/")} ` +
+        `${Url("https://a.example.ne.jp/").is_same_site("https://b.example.ne.jp/")} ` +
+        `${Url("https://a.example.com.br/").is_same_site("https://b.other.com.br/")} ` +
+        `${Url("https://App.Test.DEV./").is_same_site("https://test.dev/")} ` +
+        `${Url("http://localhost:8080/").is_same_site("http://localhost/")}`
+        "#
+        )?,
+        "false true false true false true true",
+        "it should find the registrable domain with the Public Suffix List"
+    );
+
+    Ok(())
+}
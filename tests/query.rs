@@ -0,0 +1,615 @@
+use rhai::{packages::Package, Engine, EvalAltResult};
+
+use rhai_url::UrlPackage;
+
+/**
+ * Tests query_append and query_set with non-string values
+ */
+#[test]
+fn test_query_append_set_dynamic() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    let package = UrlPackage::new();
+    package.register_into_engine(&mut engine);
+
+    assert_eq!(
+        engine.eval::<String>(
+            r#"
+        let url = Url("http://test.dev/");
+        url.query_append("page", 2);
+        url.query_append("ratio", 0.5);
+        url.query_append("debug", true);
+        url.query_append("none", ());
+        url.query
+        "#
+        )?,
+        "page=2&ratio=0.5&debug=true",
+        "it should stringify scalars and skip ()"
+    );
+
+    assert_eq!(
+        engine.eval::<String>(
+            r#"
+        let url = Url("http://test.dev/?page=1");
+        url.query_set("page", ());
+        url.href
+        "#
+        )?,
+        "http://test.dev/",
+        "it should remove the key when setting ()"
+    );
+
+    for value in [r#""z""#, "1"] {
+        assert_eq!(
+            engine.eval::<String>(&format!(
+                r#"
+        let url = Url("http://test.dev/?tag[]=x&tag=y&tags=w");
+        url.query_set("tag", {value});
+        url.query
+        "#
+            ))?,
+            format!("tags=w&tag={}", value.trim_matches('"')),
+            "it should replace the bracketed forms of the key with a {value} value"
+        );
+    }
+
+    Ok(())
+}
+
+/**
+ * Tests query_append and query_set with arrays and maps
+ */
+#[cfg(feature = "array")]
+#[test]
+fn test_query_append_set_arrays_maps() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    let package = UrlPackage::new();
+    package.register_into_engine(&mut engine);
+
+    assert_eq!(
+        engine.eval::<String>(
+            r#"
+        let url = Url("http://test.dev/");
+        url.query_append("tag", ["a", "b"]);
+        url.query_append("filter", #{ status: "open", ids: [1, 2] });
+        url.query
+        "#
+        )?,
+        "tag=a&tag=b&filter%5Bids%5D=1&filter%5Bids%5D=2&filter%5Bstatus%5D=open",
+        "it should repeat array keys and flatten maps with brackets"
+    );
+
+    assert_eq!(
+        engine.eval::<String>(
+            r#"
+        let url = Url("http://test.dev/");
+        url.query_append("a", [1, 2], "brackets");
+        url.query_append("b", [1, 2], "indices");
+        url.query_append("c", [1, 2], "comma");
+        url.query
+        "#
+        )?,
+        "a%5B%5D=1&a%5B%5D=2&b%5B0%5D=1&b%5B1%5D=2&c=1%2C2",
+        "it should use the requested array format"
+    );
+
+    assert!(
+        engine
+            .eval::<()>(r#"Url("http://test.dev/").query_append("a", [1], "unknown")"#)
+            .is_err(),
+        "it should be an error on an unknown array format"
+    );
+
+    assert_eq!(
+        engine.eval::<String>(
+            r#"
+        let url = Url("http://test.dev/?tag[]=x&page=1&tag=y&b=1");
+        url.query_set("page", 2);
+        url.query_set("tag", ["z"], "brackets");
+        url.query
+        "#
+        )?,
+        "b=1&page=2&tag%5B%5D=z",
+        "it should replace the key and its bracketed forms"
+    );
+
+    Ok(())
+}
+
+/**
+ * Tests query_get_int, query_get_float and query_get_bool
+ */
+#[test]
+fn test_query_get_typed() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    let package = UrlPackage::new();
+    package.register_into_engine(&mut engine);
+
+    assert_eq!(
+        engine.eval::<i64>(r#"Url("http://test.dev/?page=2").query_get_int("page")"#)?,
+        2,
+        "it should parse an integer"
+    );
+
+    assert!(
+        engine.eval::<bool>(r#"Url("http://test.dev/?page=two").query_get_int("page") == ()"#)?,
+        "it should return () on an invalid integer"
+    );
+
+    assert!(
+        engine.eval::<bool>(r#"Url("http://test.dev/").query_get_int("page") == ()"#)?,
+        "it should return () on a missing key"
+    );
+
+    assert_eq!(
+        engine.eval::<f64>(r#"Url("http://test.dev/?lat=45.5").query_get_float("lat")"#)?,
+        45.5,
+        "it should parse a float"
+    );
+
+    assert!(
+        engine.eval::<bool>(r#"Url("http://test.dev/?debug=on").query_get_bool("debug")"#)?,
+        "it should parse a truthy boolean"
+    );
+
+    assert!(
+        !engine.eval::<bool>(r#"Url("http://test.dev/?debug=0").query_get_bool("debug")"#)?,
+        "it should parse a falsy boolean"
+    );
+
+    assert!(
+        engine
+            .eval::<bool>(r#"Url("http://test.dev/?debug=maybe").query_get_bool("debug") == ()"#)?,
+        "it should return () on an invalid boolean"
+    );
+
+    Ok(())
+}
+
+/**
+ * Tests query_retain, query_remove_where, query_rename and query_sort
+ */
+#[test]
+fn test_query_retain_remove_where_rename_sort() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    let package = UrlPackage::new();
+    package.register_into_engine(&mut engine);
+
+    assert_eq!(
+        engine.eval::<String>(
+            r#"
+        let url = Url("http://test.dev/?utm_source=x&q=query&utm_medium=y");
+        url.query_retain(|key, value| !key.starts_with("utm_"));
+        url.query
+        "#
+        )?,
+        "q=query",
+        "it should keep the pairs accepted by the function"
+    );
+
+    assert_eq!(
+        engine.eval::<String>(
+            r#"
+        let url = Url("http://test.dev/?a=&b=");
+        url.query_remove_where(|key, value| value == "");
+        url.href
+        "#
+        )?,
+        "http://test.dev/",
+        "it should remove the '?' when every pair is removed"
+    );
+
+    assert!(
+        engine
+            .eval::<()>(r#"Url("http://test.dev/?a=1").query_retain(|key, value| 1)"#)
+            .is_err(),
+        "it should be an error when the function does not return a boolean"
+    );
+
+    assert_eq!(
+        engine.eval::<String>(
+            r#"
+        let url = Url("http://test.dev/?q=query&b=1&q=second");
+        url.query_rename("q", "search");
+        url.query
+        "#
+        )?,
+        "search=query&b=1&search=second",
+        "it should rename the key in place"
+    );
+
+    assert_eq!(
+        engine.eval::<String>(
+            r#"
+        let url = Url("http://test.dev/?b=1&a=2&b=0");
+        url.query_sort();
+        url.query
+        "#
+        )?,
+        "a=2&b=1&b=0",
+        "it should sort by key, keeping the order of repeated keys"
+    );
+
+    Ok(())
+}
+
+/**
+ * Tests query_dedupe, query_merge and query_remove_many
+ */
+#[test]
+fn test_query_dedupe_merge_remove_many() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    let package = UrlPackage::new();
+    package.register_into_engine(&mut engine);
+
+    assert_eq!(
+        engine.eval::<String>(
+            r#"
+        let url = Url("http://test.dev/?a=1&b=2&a=3");
+        url.query_dedupe();
+        url.query
+        "#
+        )?,
+        "a=1&b=2",
+        "it should keep the first value by default"
+    );
+
+    assert_eq!(
+        engine.eval::<String>(
+            r#"
+        let url = Url("http://test.dev/?a=1&b=2&a=3");
+        url.query_dedupe("keep_last");
+        url.query
+        "#
+        )?,
+        "b=2&a=3",
+        "it should keep the last value"
+    );
+
+    assert!(
+        engine
+            .eval::<()>(r#"Url("http://test.dev/?a=1").query_dedupe("keep_none")"#)
+            .is_err(),
+        "it should be an error on an unknown mode"
+    );
+
+    assert_eq!(
+        engine.eval::<String>(
+            r#"
+        let url = Url("http://test.dev/?page=1&q=query");
+        url.query_merge(#{ page: 2, sort: "asc" }, false);
+        let kept = url.query;
+        url.query_merge(#{ page: 3 }, true);
+        kept + " " + url.query
+        "#
+        )?,
+        "page=1&q=query&sort=asc q=query&sort=asc&page=3",
+        "it should only overwrite existing keys when requested"
+    );
+
+    assert_eq!(
+        engine.eval::<String>(
+            r#"
+        let url = Url("http://test.dev/?a[c]=9&q=query");
+        url.query_merge(#{ a: #{ c: 2 } }, false);
+        let kept = url.query;
+        url.query_merge(#{ a: #{ c: 1 } }, true);
+        kept + " " + url.query
+        "#
+        )?,
+        "a[c]=9&q=query q=query&a%5Bc%5D=1",
+        "it should match the bracketed forms of the keys"
+    );
+
+    #[cfg(feature = "array")]
+    assert_eq!(
+        engine.eval::<String>(
+            r#"
+        let url = Url("http://test.dev/?a=1&b=2&c=3&a=4");
+        url.query_remove_many(["a", "c"]);
+        url.query
+        "#
+        )?,
+        "b=2",
+        "it should remove every listed key"
+    );
+
+    Ok(())
+}
+
+/**
+ * Tests the params indexer
+ */
+#[test]
+fn test_params_indexer() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    let package = UrlPackage::new();
+    package.register_into_engine(&mut engine);
+
+    assert_eq!(
+        engine.eval::<String>(
+            r#"
+        let url = Url("http://test.dev/?page=1&q=a+b");
+        `${url.params["page"]} ${url.params["q"]} ${url.params["missing"] == ()}`
+        "#
+        )?,
+        "1 a b true",
+        "it should read the first value of a key"
+    );
+
+    assert_eq!(
+        engine.eval::<String>(
+            r#"
+        let url = Url("http://test.dev/?page=1&q=query&page=3");
+        url.params["page"] = 2;
+        url.params["sort"] = "name";
+        url.params["q"] = ();
+        url.href
+        "#
+        )?,
+        "http://test.dev/?page=2&sort=name",
+        "it should write the values back to the url"
+    );
+
+    assert_eq!(
+        engine.eval::<String>(
+            r#"
+        let keys = "";
+        for pair in Url("http://test.dev/?a=1&b=2").params {
+            keys += pair.key;
+        }
+        keys
+        "#
+        )?,
+        "ab",
+        "it should iterate over the pairs"
+    );
+
+    Ok(())
+}
+
+/**
+ * Tests the query edits leave the pairs they do not touch as they are
+ */
+#[test]
+fn test_query_untouched_pairs() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    let package = UrlPackage::new();
+    package.register_into_engine(&mut engine);
+
+    let cases = [
+        (r#"url.query_delete("b")"#, "a=x%20y&c=%7E"),
+        (
+            r#"url.query_set("b", "new value")"#,
+            "a=x%20y&c=%7E&b=new+value",
+        ),
+        (r#"url.query_rename("b", "d")"#, "a=x%20y&d=1+2&c=%7E"),
+        ("url.query_sort()", "a=x%20y&b=1+2&c=%7E"),
+        ("url.query_dedupe()", "a=x%20y&b=1+2&c=%7E"),
+        (r#"url.params["b"] = ()"#, "a=x%20y&c=%7E"),
+        (r#"url.query_delete("missing")"#, "a=x%20y&b=1+2&c=%7E"),
+    ];
+    for (edit, expected) in cases {
+        let script = format!(
+            r#"
+        let url = Url("http://test.dev/?a=x%20y&b=1+2&c=%7E");
+        {edit};
+        url.query
+        "#
+        );
+        assert_eq!(
+            engine.eval::<String>(&script)?,
+            expected,
+            "it should keep the encoding of the other pairs with {edit}"
+        );
+    }
+
+    assert_eq!(
+        engine.eval::<String>(
+            r#"
+        let url = Url("http://test.dev/?a=1&&b=2&");
+        url.query_delete("b");
+        url.href
+        "#
+        )?,
+        "http://test.dev/?a=1",
+        "it should drop the empty segments"
+    );
+
+    for input in ["http://test.dev/?a=1&&b=2", "http://test.dev/?"] {
+        assert_eq!(
+            engine.eval::<String>(&format!(
+                r#"
+        let url = Url("{input}");
+        url.query_delete("zz");
+        `${{url.href}} ${{url.has_query}}`
+        "#
+            ))?,
+            format!("{input} true"),
+            "it should leave {input} as it is when no pair is removed"
+        );
+    }
+
+    Ok(())
+}
+
+/**
+ * Tests the query edits keep signed Urls valid, such as S3 presigned ones
+ */
+#[test]
+fn test_query_signed_urls() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    // the limits wrap the query functions, the untouched pairs must survive them too
+    let package = UrlPackage::builder().max_query_pairs(64).build();
+    package.register_into_engine(&mut engine);
+
+    // `%20` and `+`, `~` and `%7E`, `*` and `%2A` decode the same but are signed as written
+    let signed = [
+        "X-Amz-Algorithm=AWS4-HMAC-SHA256",
+        "X-Amz-Credential=AKIAEXAMPLE%2F20240101%2Fus-east-1%2Fs3%2Faws4_request",
+        "X-Amz-Date=20240101T000000Z",
+        "X-Amz-Expires=3600",
+        "X-Amz-SignedHeaders=host",
+        "response-content-disposition=attachment%3B%20filename%3D%22cat%20one.jpg%22",
+        "tag=a~b*c+d&escaped=%7E%2A%20",
+        "X-Amz-Signature=0f1e2d3c4b5a",
+    ]
+    .join("&");
+    let input = format!("https://bucket.s3.amazonaws.com/cat%20one.jpg?{signed}&utm_source=mail");
+
+    let mut sorted: Vec<_> = format!("{signed}&utm_source=mail")
+        .split('&')
+        .map(String::from)
+        .collect();
+    sorted.sort_by(|a, b| a.split('=').next().cmp(&b.split('=').next()));
+    let sorted = sorted.join("&");
+
+    let cases = [
+        (r#"url.query_delete("utm_source")"#, signed.clone()),
+        (r#"url.query_remove("utm_source")"#, signed.clone()),
+        (
+            r#"url.query_retain(|key, value| !key.starts_with("utm_"))"#,
+            signed.clone(),
+        ),
+        (
+            r#"url.query_remove_where(|key, value| value == "mail")"#,
+            signed.clone(),
+        ),
+        (r#"url.params["utm_source"] = ()"#, signed.clone()),
+        (
+            r#"url = url.without_query_param("utm_source")"#,
+            signed.clone(),
+        ),
+        (
+            r#"url.query_set("utm_source", "web")"#,
+            format!("{signed}&utm_source=web"),
+        ),
+        (
+            r#"url.params["utm_source"] = "web""#,
+            format!("{signed}&utm_source=web"),
+        ),
+        (
+            r#"url = url.with_query_param("utm_source", "web")"#,
+            format!("{signed}&utm_source=web"),
+        ),
+        (
+            r#"url.query_merge(#{ utm_source: "web" }, true)"#,
+            format!("{signed}&utm_source=web"),
+        ),
+        (
+            r#"url.query_merge(#{ utm_medium: "email" }, false)"#,
+            format!("{signed}&utm_source=mail&utm_medium=email"),
+        ),
+        (
+            r#"url.query_append("utm_campaign", "spring sale")"#,
+            format!("{signed}&utm_source=mail&utm_campaign=spring+sale"),
+        ),
+        (
+            r#"url.query_rename("utm_source", "source")"#,
+            format!("{signed}&source=mail"),
+        ),
+        ("url.query_dedupe()", format!("{signed}&utm_source=mail")),
+        ("url.query_sort()", sorted),
+    ];
+    for (edit, expected) in cases {
+        let script = format!(
+            r#"
+        let url = Url("{input}");
+        {edit};
+        url.query
+        "#
+        );
+        assert_eq!(
+            engine.eval::<String>(&script)?,
+            expected,
+            "it should keep the signed pairs byte for byte with {edit}"
+        );
+    }
+
+    #[cfg(feature = "array")]
+    assert_eq!(
+        engine.eval::<String>(&format!(
+            r#"
+        let url = Url("{input}");
+        url.query_remove_many(["utm_source", "utm_medium"]);
+        url.query
+        "#
+        ))?,
+        signed,
+        "it should keep the signed pairs byte for byte with query_remove_many"
+    );
+
+    assert_eq!(
+        engine.eval::<String>(&format!(
+            r#"
+        let url = Url("{input}");
+        url.query_set("X-Amz-Expires", "60");
+        url.query_get("tag") + "|" + url.query_get("escaped")
+        "#
+        ))?,
+        "a~b*c d|~* ",
+        "it should decode the kept pairs as before"
+    );
+
+    Ok(())
+}
+
+/**
+ * Tests the query edits that change no pair keep the exact bytes of the query
+ */
+#[test]
+fn test_query_noop_edits() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    let package = UrlPackage::new();
+    package.register_into_engine(&mut engine);
+
+    let inputs = [
+        "X-Amz-Credential=AKIA%2F2024&&X-Amz-Signature=abc",
+        "a=x%20y&b=1+2&",
+        "&a=%7E&&b=~&",
+        "",
+    ];
+    let edits = [
+        r#"url.query_delete("missing")"#,
+        r#"url.query_retain(|key, value| true)"#,
+        r#"url.query_remove_where(|key, value| false)"#,
+        r#"url.query_rename("missing", "other")"#,
+        r#"url.query_merge(#{ a: 1 }, false)"#,
+        r#"url.params["missing"] = ()"#,
+        "url.query_dedupe()",
+        r#"url.query_dedupe("keep_last")"#,
+        "url.query_sort()",
+    ];
+
+    for input in inputs {
+        for edit in edits {
+            // `query_merge` only adds missing keys
+            if edit.contains("query_merge") && !input.contains("a=") {
+                continue;
+            }
+
+            let script = format!(
+                r#"
+        let url = Url("http://test.dev/?{input}");
+        {edit};
+        url.href
+        "#
+            );
+            assert_eq!(
+                engine.eval::<String>(&script)?,
+                format!("http://test.dev/?{input}"),
+                "it should keep the exact query {input:?} with {edit}"
+            );
+        }
+    }
+
+    Ok(())
+}
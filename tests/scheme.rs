@@ -0,0 +1,189 @@
+use rhai::{packages::Package, Engine, EvalAltResult, INT};
+
+use rhai_url::{SchemeRegistry, UrlPackage};
+
+/**
+ * Tests the effective port of special and custom schemes
+ */
+#[test]
+fn test_effective_port() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    let package = UrlPackage::builder()
+        .register_scheme("redis", 6379)
+        .register_scheme("AMQP", 5672)
+        .build();
+    package.register_into_engine(&mut engine);
+
+    assert_eq!(
+        engine.eval::<INT>(r#"Url("https://test.dev/").effective_port"#)?,
+        443,
+        "it should return the default port of a special scheme"
+    );
+
+    assert_eq!(
+        engine.eval::<INT>(r#"Url("redis://cache.test.dev/0").effective_port"#)?,
+        6379,
+        "it should return the default port of a registered scheme"
+    );
+
+    assert_eq!(
+        engine.eval::<INT>(r#"Url("amqp://queue.test.dev:5673").effective_port"#)?,
+        5673,
+        "it should return the explicit port"
+    );
+
+    assert_eq!(
+        engine.eval::<()>(r#"Url("postgres://db.test.dev/app").effective_port"#)?,
+        (),
+        "it should return () for an unknown scheme"
+    );
+
+    Ok(())
+}
+
+/**
+ * Tests the normalization of registered default ports
+ */
+#[test]
+fn test_normalize_default_port() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    let package = UrlPackage::builder().script_schemes(true).build();
+    package.register_into_engine(&mut engine);
+
+    assert_eq!(
+        engine.eval::<String>(
+            r#"
+        register_scheme("postgres", 5432);
+
+        let url = Url("postgres://user@db.test.dev:5432/app");
+        url.href + " " + url.effective_port
+        "#
+        )?,
+        "postgres://user@db.test.dev/app 5432",
+        "it should drop the default port of a scheme registered by a script"
+    );
+
+    assert_eq!(
+        engine.eval::<String>(
+            r#"
+        register_scheme("redis", 6379);
+
+        Url("redis://cache.test.dev:6380/").with_port(6379).href
+        "#
+        )?,
+        "redis://cache.test.dev/",
+        "it should drop the default port set by with_port"
+    );
+
+    assert!(
+        engine
+            .eval::<()>(r#"register_scheme("http", 8080)"#)
+            .is_err(),
+        "it should refuse to register a special scheme"
+    );
+
+    assert!(
+        engine
+            .eval::<()>(r#"register_scheme("redis", 70000)"#)
+            .is_err(),
+        "it should refuse an invalid port"
+    );
+
+    Ok(())
+}
+
+/**
+ * Tests a registry shared with the host
+ */
+#[test]
+fn test_shared_registry() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    let schemes = SchemeRegistry::new();
+    let package = UrlPackage::builder()
+        .scheme_registry(schemes.clone())
+        .script_schemes(true)
+        .build();
+    package.register_into_engine(&mut engine);
+
+    schemes.register("mongodb", 27017)?;
+
+    assert_eq!(
+        engine.eval::<String>(r#"Url("mongodb://db.test.dev:27017/").href"#)?,
+        "mongodb://db.test.dev/",
+        "it should honor schemes registered by the host after the build"
+    );
+
+    engine.eval::<()>(r#"register_scheme("amqp", 5672)"#)?;
+
+    assert_eq!(
+        schemes.default_port("amqp"),
+        None,
+        "it should not share schemes registered by scripts with the host"
+    );
+
+    assert!(
+        engine
+            .eval::<()>(r#"register_scheme("mongodb", 1)"#)
+            .is_err(),
+        "it should refuse to replace a scheme of the host"
+    );
+
+    Ok(())
+}
+
+/**
+ * Tests scripts cannot register schemes unless enabled, and only for their package
+ */
+#[test]
+fn test_script_schemes() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    let package = UrlPackage::new();
+    package.register_into_engine(&mut engine);
+
+    assert!(
+        engine
+            .eval::<()>(r#"register_scheme("postgres", 1)"#)
+            .is_err(),
+        "it should not register register_scheme by default"
+    );
+
+    let schemes = SchemeRegistry::new();
+    let tenant = |schemes: &SchemeRegistry| {
+        let mut engine = Engine::new();
+        UrlPackage::builder()
+            .scheme_registry(schemes.clone())
+            .script_schemes(true)
+            .build()
+            .register_into_engine(&mut engine);
+        engine
+    };
+    let (a, b) = (tenant(&schemes), tenant(&schemes));
+
+    a.eval::<()>(r#"register_scheme("postgres", 1)"#)?;
+
+    assert_eq!(
+        a.eval::<String>(r#"Url("postgres://db:1/app").href"#)?,
+        "postgres://db/app",
+        "it should honor the scheme in the package of the script"
+    );
+
+    assert_eq!(
+        b.eval::<String>(r#"Url("postgres://db:1/app").href"#)?,
+        "postgres://db:1/app",
+        "it should not change the Urls of other packages"
+    );
+
+    schemes.register("redis", 6379)?;
+
+    assert_eq!(
+        b.eval::<INT>(r#"Url("redis://cache/0").effective_port"#)?,
+        6379,
+        "it should still honor the schemes of the host"
+    );
+
+    Ok(())
+}
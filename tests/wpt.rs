@@ -0,0 +1,346 @@
+//! Data-driven tests imported from web-platform-tests, run through the Rhai API.
+//!
+//! `tests/wpt` holds a vendored copy of `urltestdata.json` and `setters_tests.json`,
+//! see https://github.com/web-platform-tests/wpt/tree/master/url/resources.
+//! Inputs with a base are resolved with `urls_join`, under the `array` feature.
+//! The Url properties and the browser properties of `web_compat` are both tested,
+//! known differences are listed in `expected_failures.txt` and
+//! `expected_failures_web_compat.txt`, using the names of the failure reports.
+
+use rhai::{packages::Package, Dynamic, Engine, Map, Scope};
+use serde_json::Value;
+
+use rhai_url::{UrlErrorKind, UrlPackage};
+
+/// The properties of the browser `URL` object, registered by `web_compat`.
+const WEB_PROPERTIES: [&str; 10] = [
+    "href", "protocol", "username", "password", "host", "hostname", "port", "pathname", "search",
+    "hash",
+];
+
+/// Creates an engine with the package, with or without the browser properties.
+fn engine(web_compat: bool) -> Engine {
+    let mut engine = Engine::new();
+
+    let package = UrlPackage::builder().web_compat(web_compat).build();
+    package.register_into_engine(&mut engine);
+
+    engine
+}
+
+/// The script creating `url` from `input`, resolved against `base` with `urls_join`
+/// when the test has one.
+fn constructor(test: &Value) -> &'static str {
+    match test["base"].is_null() {
+        true => "Url(input)",
+        false => {
+            r#"{ let url = urls_join(Url(base), [input])[0]; if type_of(url) == "()" { throw "invalid url"; } url }"#
+        }
+    }
+}
+
+/// Reads the script-level components of the Url created by `url`, after `setup` ran.
+fn components(engine: &Engine, scope: &mut Scope, url: &str, setup: &str) -> Result<Map, String> {
+    let script = format!(
+        "let url = {url}; {setup}
+        #{{ href: url.href, scheme: url.scheme, domain: url.domain, path: url.path, query: url.query, fragment: url.fragment }}"
+    );
+
+    engine
+        .eval_with_scope::<Map>(scope, &script)
+        .map_err(|e| e.to_string())
+}
+
+/// Reads the browser properties of the Url created by `url`, after `setup` ran.
+fn web_properties(
+    engine: &Engine,
+    scope: &mut Scope,
+    url: &str,
+    setup: &str,
+) -> Result<Map, String> {
+    let properties = WEB_PROPERTIES.map(|name| format!("{name}: url.{name}"));
+    let script = format!("let url = {url}; {setup} #{{ {} }}", properties.join(", "));
+
+    engine
+        .eval_with_scope::<Map>(scope, &script)
+        .map_err(|e| e.to_string())
+}
+
+/// Compares the browser properties against the WPT expectations, ignoring missing keys.
+fn check_web(properties: &Map, expected: &Value) -> Result<(), String> {
+    for key in WEB_PROPERTIES {
+        let Some(expected) = expected[key].as_str() else {
+            continue;
+        };
+        let actual = properties[key].to_string();
+        if actual != expected {
+            return Err(format!("url.{key}: expected {expected:?}, got {actual:?}"));
+        }
+    }
+
+    Ok(())
+}
+
+/// Compares the components against the WPT expectations, ignoring missing keys.
+fn check(components: &Map, expected: &Value) -> Result<(), String> {
+    let text = |key: &str| expected[key].as_str().map(str::to_string);
+
+    // `domain` is empty for ip addresses, the hosts of other schemes are opaque
+    let special = ["http:", "https:", "ws:", "wss:", "ftp:", "file:"];
+    let is_special = text("protocol").is_some_and(|p| special.contains(&p.as_str()));
+    let domain = text("hostname").map(|hostname| {
+        let is_ipv4 = is_special && hostname.parse::<std::net::Ipv4Addr>().is_ok();
+        if is_ipv4 || hostname.starts_with('[') {
+            String::new()
+        } else {
+            hostname
+        }
+    });
+
+    // script names of the properties of the browser `URL` object
+    let checks = [
+        ("href", text("href")),
+        (
+            "scheme",
+            text("protocol").map(|p| p.trim_end_matches(':').to_string()),
+        ),
+        ("domain", domain),
+        ("path", text("pathname")),
+        (
+            "query",
+            text("search").map(|s| s.strip_prefix('?').unwrap_or(&s).to_string()),
+        ),
+        (
+            "fragment",
+            text("hash").map(|h| h.strip_prefix('#').unwrap_or(&h).to_string()),
+        ),
+    ];
+
+    for (key, expected) in checks {
+        let Some(expected) = expected else { continue };
+        let actual = components[key].to_string();
+        if actual != expected {
+            return Err(format!("url.{key}: expected {expected:?}, got {actual:?}"));
+        }
+    }
+
+    Ok(())
+}
+
+/// Runs a test of `urltestdata.json`, with the Url or the browser properties.
+///
+/// Inputs with a base are resolved with `urls_join`, which gives `()` for invalid Urls.
+fn run_url_test(engine: &Engine, test: &Value, web_compat: bool) -> Result<(), String> {
+    let input = test["input"].as_str().unwrap();
+    let mut scope = Scope::new();
+    scope.push_constant("input", input.to_string());
+    if let Some(base) = test["base"].as_str() {
+        scope.push_constant("base", base.to_string());
+    }
+
+    if test["failure"].as_bool() == Some(true) {
+        let script = match test["base"].is_null() {
+            true => "Url(input)",
+            false => "urls_join(Url(base), [input])[0]",
+        };
+        return match engine.eval_with_scope::<Dynamic>(&mut scope, script) {
+            Ok(url) if url.is_unit() => Ok(()),
+            Ok(url) => Err(format!("expected an error, got {url}")),
+            Err(err) if UrlErrorKind::of(&err) == Some(UrlErrorKind::InvalidUrl) => Ok(()),
+            Err(err) => Err(format!("expected an invalid_url error, got {err}")),
+        };
+    }
+
+    let read = if web_compat {
+        web_properties
+    } else {
+        components
+    };
+
+    let parsed = read(engine, &mut scope, constructor(test), "")?;
+    if web_compat {
+        check_web(&parsed, test)?;
+    } else {
+        check(&parsed, test)?;
+    }
+
+    // parsing the serialization again gives the same Url
+    let href = parsed["href"].to_string();
+    let mut scope = Scope::new();
+    scope.push_constant("input", href.clone());
+    let reparsed = read(engine, &mut scope, "Url(input)", "")?;
+    if reparsed["href"].to_string() != href {
+        return Err(format!("reparsed as {}", reparsed["href"]));
+    }
+
+    Ok(())
+}
+
+/// Runs a test of `setters_tests.json` with a script property.
+///
+/// The values are given as the browser `URL` setters receive them: the delimiter is
+/// optional, and an empty value removes the component, written `()` in scripts.
+fn run_setter_test(
+    engine: &Engine,
+    property: &str,
+    setter: &str,
+    test: &Value,
+) -> Result<(), String> {
+    let value = test["new_value"].as_str().unwrap();
+    let value: Dynamic = match property {
+        "protocol" => value.split(':').next().unwrap().into(),
+        "search" | "hash" if value.is_empty() => Dynamic::UNIT,
+        "search" => value.strip_prefix('?').unwrap_or(value).into(),
+        "hash" => value.strip_prefix('#').unwrap_or(value).into(),
+        _ => value.into(),
+    };
+
+    let mut scope = Scope::new();
+    scope.push_constant("input", test["href"].as_str().unwrap().to_string());
+    scope.push_constant("value", value);
+
+    let components = components(
+        engine,
+        &mut scope,
+        "Url(input)",
+        &format!("url.{setter} = value;"),
+    )?;
+    check(&components, &test["expected"])
+}
+
+/// Runs a test of `setters_tests.json` with a browser property, given the value verbatim.
+fn run_web_setter_test(engine: &Engine, property: &str, test: &Value) -> Result<(), String> {
+    let mut scope = Scope::new();
+    scope.push_constant("input", test["href"].as_str().unwrap().to_string());
+    scope.push_constant("value", test["new_value"].as_str().unwrap().to_string());
+
+    let properties = web_properties(
+        engine,
+        &mut scope,
+        "Url(input)",
+        &format!("url.{property} = value;"),
+    )?;
+    check_web(&properties, &test["expected"])
+}
+
+/// Records the result of a test, failing if it differs from the expected failures.
+fn report(
+    name: String,
+    result: Result<(), String>,
+    errors: &mut Vec<String>,
+    expected_failures: &mut Vec<&str>,
+) {
+    let expected_failure = expected_failures.contains(&name.as_str());
+    expected_failures.retain(|failure| *failure != name);
+
+    match (result, expected_failure) {
+        (Ok(()), true) => errors.push(format!("{name}\n  passed, expected a failure")),
+        (Err(err), false) => errors.push(format!("{name}\n  {err}")),
+        _ => {}
+    }
+}
+
+/// Runs the web-platform-tests, failing on results differing from the expected failures.
+fn run_wpt(web_compat: bool, expected_failures: &str) {
+    let engine = engine(web_compat);
+
+    let mut expected_failures = expected_failures
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        // the tests with a base are only run with `urls_join`
+        .filter(|line| cfg!(feature = "array") || !line.contains("> against <"))
+        .collect::<Vec<_>>();
+    let mut errors = Vec::new();
+
+    let url_tests: Value = serde_json::from_str(include_str!("wpt/urltestdata.json")).unwrap();
+    for test in url_tests.as_array().unwrap() {
+        // strings are comments, tests with a base are resolved with `urls_join`
+        if !test.is_object() || (cfg!(not(feature = "array")) && !test["base"].is_null()) {
+            continue;
+        }
+
+        let mut name = format!("<{}>", test["input"].as_str().unwrap().escape_default());
+        if let Some(base) = test["base"].as_str() {
+            name += &format!(" against <{}>", base.escape_default());
+        }
+        report(
+            name,
+            run_url_test(&engine, test, web_compat),
+            &mut errors,
+            &mut expected_failures,
+        );
+    }
+
+    let setter_tests: Value = serde_json::from_str(include_str!("wpt/setters_tests.json")).unwrap();
+    let name = |setter: &str, test: &Value| {
+        format!(
+            "<{}> set {setter} to <{}>",
+            test["href"].as_str().unwrap().escape_default(),
+            test["new_value"].as_str().unwrap().escape_default()
+        )
+    };
+
+    if web_compat {
+        for (property, tests) in setter_tests.as_object().unwrap() {
+            if property == "comment" {
+                continue;
+            }
+            for test in tests.as_array().unwrap() {
+                report(
+                    name(property, test),
+                    run_web_setter_test(&engine, property, test),
+                    &mut errors,
+                    &mut expected_failures,
+                );
+            }
+        }
+    } else {
+        // the script properties covering the WPT setters
+        let setters = [
+            ("protocol", "scheme"),
+            ("pathname", "path"),
+            ("search", "query"),
+            ("hash", "fragment"),
+            ("hash", "hash"),
+        ];
+
+        for (property, setter) in setters {
+            for test in setter_tests[property].as_array().unwrap() {
+                report(
+                    name(setter, test),
+                    run_setter_test(&engine, property, setter, test),
+                    &mut errors,
+                    &mut expected_failures,
+                );
+            }
+        }
+    }
+
+    assert!(
+        errors.is_empty(),
+        "it should match the web-platform-tests, {} unexpected results:\n\n{}",
+        errors.len(),
+        errors.join("\n")
+    );
+    assert!(
+        expected_failures.is_empty(),
+        "it should run every expected failure, not found:\n\n{}",
+        expected_failures.join("\n")
+    );
+}
+
+/**
+ * Tests the WHATWG Url parsing and setters through the Rhai API
+ */
+#[test]
+fn test_wpt() {
+    run_wpt(false, include_str!("wpt/expected_failures.txt"));
+}
+
+/**
+ * Tests the browser properties of web_compat against the WHATWG Url parsing and setters
+ */
+#[test]
+fn test_wpt_web_compat() {
+    run_wpt(true, include_str!("wpt/expected_failures_web_compat.txt"));
+}